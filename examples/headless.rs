@@ -0,0 +1,46 @@
+//! Plays a seeded game with the built-in AI, without a terminal, and prints
+//! the result: `cargo run --example headless -- [SEED]`.
+
+use std::time::Duration;
+
+use tetris::ai::Bot;
+use tetris::{GameConfig, GameEvent, GameState};
+
+/// Simulation step, matching the terminal front end.
+const STEP: Duration = Duration::from_millis(10);
+/// Steps between AI moves.
+const AI_EVERY: u32 = 5;
+/// Give up after this much game time.
+const TIME_LIMIT: Duration = Duration::from_secs(600);
+
+fn main() {
+    let seed = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(1);
+    let mut game = GameState::new(GameConfig { seed: Some(seed), ..GameConfig::default() });
+    let mut bot = Bot::default();
+
+    let mut steps = 0u32;
+    while !game.game_over && game.stats.elapsed < TIME_LIMIT {
+        if steps.is_multiple_of(AI_EVERY) {
+            bot.step(&mut game);
+        }
+        for event in game.step(STEP) {
+            if matches!(event, GameEvent::LinesCleared { kind, .. } if kind.perfect_clear) {
+                println!("{:>8.2}s  perfect clear", game.stats.elapsed.as_secs_f32());
+            }
+        }
+        steps += 1;
+    }
+
+    println!(
+        "seed {}: score {}, level {}, {} pieces, {} lines{}",
+        seed,
+        game.score,
+        game.level,
+        game.piece_count,
+        game.stats.lines_cleared,
+        match game.top_out {
+            Some(reason) => format!(", {}", reason),
+            None => String::new(),
+        }
+    );
+}
@@ -1,236 +1,310 @@
-use crate::game::{GameState, FIELD_HEIGHT, FIELD_WIDTH, TETROMINOES};
+use serde::{Deserialize, Serialize};
 
-/// Returns the (rotation, x) pair that maximises the heuristic score for the
-/// current piece, using one-piece lookahead with the next piece.
-pub fn compute_best_move(game: &GameState) -> (usize, i32) {
-    let mut best_score = f64::NEG_INFINITY;
-    let mut best_rotation = game.current_rotation;
-    let mut best_x = game.current_x;
+use crate::bitboard::BitBoard;
+use crate::game::{Command, GameState};
+use crate::pieces::PieceDef;
+use crate::rotation::{RotateDir, RotationSystem};
 
-    for rotation in 0..4usize {
-        for x in -2..(FIELD_WIDTH as i32) {
-            if !piece_fits_field(&game.field, game.current_piece, rotation, x, 0) {
-                continue;
-            }
+/// How many queued pieces the AI looks ahead through.
+const MAX_LOOKAHEAD: usize = 3;
+/// Placements expanded at each lookahead level (best first); the rest are
+/// scored statically only.
+const LOOKAHEAD_BEAM: usize = 4;
+/// Weight of each lookahead level relative to the one before it.
+const LOOKAHEAD_DISCOUNT: f64 = 0.5;
+/// The stack enters the danger zone this many rows below the top of the
+/// visible area.
+const DANGER_MARGIN: i32 = 5;
 
-            let mut drop_y = 0i32;
-            while piece_fits_field(&game.field, game.current_piece, rotation, x, drop_y + 1) {
-                drop_y += 1;
-            }
+/// Weights of the board features the AI scores placements by; a
+/// placement's score is the sum of each feature times its weight.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+    /// Sum of the column heights.
+    pub aggregate_height: f64,
+    pub lines_cleared: f64,
+    pub holes: f64,
+    /// Burial depth: filled cells above each hole, summed.
+    pub covered_holes: f64,
+    /// Sum of height differences between neighbouring columns.
+    pub bumpiness: f64,
+    /// Each row the stack reaches into the danger zone near the top.
+    pub danger: f64,
+}
 
-            let (locked_field, lines) =
-                simulate_lock(&game.field, game.current_piece, rotation, x, drop_y);
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            aggregate_height: -0.510066,
+            lines_cleared: 0.760666,
+            holes: -0.75,           // was -0.356630; holes are catastrophic
+            covered_holes: -0.35,   // extra penalty for deeply buried holes
+            bumpiness: -0.356630,   // was -0.184483; high bumpiness blocks future pieces
+            danger: -3.0,
+        }
+    }
+}
 
-            // One-piece lookahead: best score achievable with the next piece
-            let next_best = best_placement_score(&locked_field, game.next_piece);
-            let score = score_field(&locked_field, lines) + 0.5 * next_best;
+/// A placement chosen by the AI: optionally swap with the hold slot, line up
+/// `rotation` and `x` near the top and drop.  When `spin` is set, the piece
+/// is turned once more after reaching the floor so a kick can carry it
+/// somewhere a straight drop cannot reach.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AiTarget {
+    pub hold: bool,
+    pub rotation: usize,
+    pub x: i32,
+    pub spin: Option<RotateDir>,
+}
+
+/// Plays a game through `Command`s: plans a placement for each new piece
+/// with `compute_best_move`, then steers the piece there a few moves at a
+/// time.
+#[derive(Debug, Clone, Default)]
+pub struct Bot {
+    pub weights: Weights,
+    target: AiTarget,
+    /// `piece_count` of the piece the current plan is for.
+    planned_for: Option<u32>,
+}
 
-            if score > best_score {
-                best_score = score;
-                best_rotation = rotation;
-                best_x = x;
+impl Bot {
+    /// Drop the current plan so the next step makes a fresh one, e.g. when
+    /// the bot takes over from the player mid-piece.
+    pub fn reset(&mut self) {
+        self.planned_for = None;
+    }
+
+    /// One AI move, issued at a fixed interval (gravity still comes from
+    /// `GameState::step`).  Swaps with the hold slot first if the plan asks
+    /// for it, then rotates and slides 2 steps toward the target per move,
+    /// then hard-drops once aligned.  If the plan includes a spin, the piece
+    /// is first dropped to the floor and turned there so the kick carries
+    /// it into its final slot.
+    pub fn step(&mut self, game: &mut GameState) {
+        // Nothing to steer between pieces; rotating or holding here would
+        // be buffered and applied to the next piece.
+        if game.paused || !game.piece_active() {
+            return;
+        }
+        if self.planned_for != Some(game.piece_count) {
+            self.target = compute_best_move(game, &self.weights);
+            self.planned_for = Some(game.piece_count);
+        }
+        // 0. Swap with the hold slot if the plan places the other piece
+        if self.target.hold {
+            self.target.hold = false;
+            game.apply(Command::Hold);
+        }
+        // 1. Rotate toward target rotation
+        if game.current_rotation != self.target.rotation {
+            let dir = if (game.current_rotation + 3) % 4 == self.target.rotation {
+                RotateDir::Ccw
+            } else {
+                RotateDir::Cw
+            };
+            game.apply(Command::Rotate(dir));
+        }
+        // 2. Slide 2 steps toward target x per move
+        for _ in 0..2 {
+            if game.current_x < self.target.x {
+                game.apply(Command::MoveRight);
+            } else if game.current_x > self.target.x {
+                game.apply(Command::MoveLeft);
+            }
+        }
+        // 3. Hard-drop once aligned
+        if game.current_rotation == self.target.rotation && game.current_x == self.target.x {
+            if let Some(dir) = self.target.spin.take() {
+                game.apply(Command::SonicDrop);
+                game.apply(Command::Rotate(dir));
             }
+            game.apply(Command::HardDrop);
+        }
+    }
+}
+
+/// Returns the placement that maximises the heuristic score for the current
+/// piece under `weights`, looking ahead through the preview queue.  If the
+/// hold slot is available, placing the swapped-in piece instead is scored as
+/// well.
+pub fn compute_best_move(game: &GameState, weights: &Weights) -> AiTarget {
+    let pieces = &game.config.pieces;
+    let current = pieces.get(game.current_piece);
+    let queue: Vec<&PieceDef> = game
+        .next_queue
+        .iter()
+        .take(MAX_LOOKAHEAD)
+        .map(|&p| pieces.get(p))
+        .collect();
+
+    let (best_score, mut best) = best_target(game, weights, current, &queue);
+
+    if !game.hold_used {
+        // Holding brings in the held piece, or the front of the queue when
+        // the slot is empty.  In the latter case the held piece stands in for
+        // the lookahead if the queue has nothing left.
+        let (incoming, lookahead) = match game.hold_piece {
+            Some(held) => (pieces.get(held), queue.clone()),
+            None if queue.len() > 1 => (queue[0], queue[1..].to_vec()),
+            None => (queue[0], vec![current]),
+        };
+        let (score, target) = best_target(game, weights, incoming, &lookahead);
+        if score > best_score {
+            best = AiTarget { hold: true, ..target };
         }
     }
 
-    (best_rotation, best_x)
+    best
 }
 
-/// Best score achievable by placing `piece` on `field` in any rotation/column.
-fn best_placement_score(field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT], piece: usize) -> f64 {
-    let mut best = f64::NEG_INFINITY;
+/// Best placement of `piece` on the current field, scored with lookahead
+/// through `queue`.
+fn best_target(game: &GameState, weights: &Weights, piece: &PieceDef, queue: &[&PieceDef]) -> (f64, AiTarget) {
+    let mut best_score = f64::NEG_INFINITY;
+    let mut best = AiTarget {
+        rotation: game.current_rotation,
+        x: game.current_x,
+        ..AiTarget::default()
+    };
+
+    let board = game.field.bits();
+    let danger_line = game.config.board.height as i32 - DANGER_MARGIN;
+    let mut consider = |x: i32, rotation: usize, spin: Option<RotateDir>, drop_pos: (usize, i32, i32)| {
+        let (r, px, py) = drop_pos;
+        let (locked_field, lines) = simulate_lock(board, piece, r, px, py);
+
+        // Lookahead: best score achievable with the queued pieces
+        let next_best = best_placement_score(&locked_field, queue, weights, danger_line);
+        let score = score_field(&locked_field, lines, weights, danger_line) + LOOKAHEAD_DISCOUNT * next_best;
+
+        if score > best_score {
+            best_score = score;
+            best = AiTarget { hold: false, rotation, x, spin };
+        }
+    };
+
     for rotation in 0..4usize {
-        for x in -2..(FIELD_WIDTH as i32) {
-            if !piece_fits_field(field, piece, rotation, x, 0) {
+        for x in column_range(board, piece) {
+            let Some(drop_y) = straight_drop(board, piece, rotation, x) else {
                 continue;
-            }
-            let mut drop_y = 0i32;
-            while piece_fits_field(field, piece, rotation, x, drop_y + 1) {
-                drop_y += 1;
-            }
-            let (locked, lines) = simulate_lock(field, piece, rotation, x, drop_y);
-            let s = score_field(&locked, lines);
-            if s > best {
-                best = s;
+            };
+            consider(x, rotation, None, (rotation, x, drop_y));
+
+            for dir in [RotateDir::Cw, RotateDir::Ccw] {
+                if let Some(pos) = spin_at_floor(board, game.config.rotation, piece, rotation, x, drop_y, dir) {
+                    consider(x, rotation, Some(dir), pos);
+                }
             }
         }
     }
-    if best == f64::NEG_INFINITY { 0.0 } else { best }
+
+    (best_score, best)
 }
 
-/// Check whether `piece` at (rotation, pos_x, pos_y) fits in an arbitrary field
-/// (no GameState required, so it can be used on simulated boards).
-fn piece_fits_field(
-    field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT],
-    piece: usize,
-    rotation: usize,
-    pos_x: i32,
-    pos_y: i32,
-) -> bool {
-    let tetromino = TETROMINOES[piece];
-    for px in 0..4usize {
-        for py in 0..4usize {
-            let pi = GameState::rotate(px, py, rotation);
-            let fi_x = pos_x + px as i32;
-            let fi_y = pos_y + py as i32;
-            if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                if fi_x < 0
-                    || fi_x >= FIELD_WIDTH as i32
-                    || fi_y < 0
-                    || fi_y >= FIELD_HEIGHT as i32
-                {
-                    return false;
-                }
-                if field[fi_y as usize][fi_x as usize] != 0 {
-                    return false;
-                }
-            }
-        }
+/// Every piece-origin column that could put at least one cell on the board.
+fn column_range(field: &BitBoard, piece: &PieceDef) -> std::ops::Range<i32> {
+    -(piece.size as i32)..(field.cols() as i32 + 2)
+}
+
+/// Resting row for `piece` dropped straight down from the top at
+/// (rotation, x), or `None` if it does not fit at the top.
+fn straight_drop(field: &BitBoard, piece: &PieceDef, rotation: usize, x: i32) -> Option<i32> {
+    let mask = piece.mask(rotation);
+    if !field.fits(mask, x, 0) {
+        return None;
     }
-    true
+    Some(field.drop_distance(mask, x, 0))
 }
 
-/// Clones the field, writes the piece at (rotation, x, y), clears complete
-/// lines, and returns the resulting field together with the number of lines
-/// cleared.
-fn simulate_lock(
-    field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT],
-    piece: usize,
+/// Turn a piece resting at (rotation, x, y) using the rotation system's kicks
+/// and let it fall again.  Returns the final position only when it differs
+/// from every straight-drop placement, i.e. the kick made it reachable.
+fn spin_at_floor(
+    field: &BitBoard,
+    system: RotationSystem,
+    piece: &PieceDef,
     rotation: usize,
     x: i32,
     y: i32,
-) -> ([[u8; FIELD_WIDTH]; FIELD_HEIGHT], u32) {
-    let mut f = *field;
-    let tetromino = TETROMINOES[piece];
-
-    for px in 0..4usize {
-        for py in 0..4usize {
-            let pi = GameState::rotate(px, py, rotation);
-            if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                let fx = x + px as i32;
-                let fy = y + py as i32;
-                if fx >= 0 && fx < FIELD_WIDTH as i32 && fy >= 0 && fy < FIELD_HEIGHT as i32 {
-                    f[fy as usize][fx as usize] = (piece + 1) as u8;
-                }
-            }
-        }
+    dir: RotateDir,
+) -> Option<(usize, i32, i32)> {
+    let (r, kx, ky, _) = system.try_rotate(piece.kicks, rotation, x, y, dir, |r, x, y| {
+        field.fits(piece.mask(r), x, y)
+    })?;
+    let land_y = ky + field.drop_distance(piece.mask(r), kx, ky);
+    if straight_drop(field, piece, r, kx) == Some(land_y) {
+        return None;
     }
-
-    let lines = clear_lines_sim(&mut f);
-    (f, lines)
+    Some((r, kx, land_y))
 }
 
-/// Removes complete interior rows (values != 0) and returns how many were removed.
-fn clear_lines_sim(field: &mut [[u8; FIELD_WIDTH]; FIELD_HEIGHT]) -> u32 {
-    let mut lines = 0u32;
-    let mut row = (FIELD_HEIGHT - 2) as i32; // last playfield row (exclude border)
-    while row >= 0 {
-        let complete = (1..FIELD_WIDTH - 1).all(|c| field[row as usize][c] != 0);
-        if complete {
-            // Shift everything above this row down by one
-            for r in (1..=row as usize).rev() {
-                for c in 1..(FIELD_WIDTH - 1) {
-                    field[r][c] = field[r - 1][c];
-                }
-            }
-            for c in 1..(FIELD_WIDTH - 1) {
-                field[0][c] = 0;
-            }
-            lines += 1;
-            // Re-check the same row index (it now contains the row that was above)
-        } else {
-            row -= 1;
+/// Best score achievable by placing the first piece of `queue` on `field` in
+/// any rotation/column, plus the discounted best score of the remaining
+/// pieces.  Only the `LOOKAHEAD_BEAM` best placements are searched deeper.
+fn best_placement_score(field: &BitBoard, queue: &[&PieceDef], weights: &Weights, danger_line: i32) -> f64 {
+    let Some((&piece, rest)) = queue.split_first() else {
+        return 0.0;
+    };
+
+    let mut candidates = Vec::new();
+    for rotation in 0..4usize {
+        for x in column_range(field, piece) {
+            let Some(drop_y) = straight_drop(field, piece, rotation, x) else {
+                continue;
+            };
+            let (locked, lines) = simulate_lock(field, piece, rotation, x, drop_y);
+            candidates.push((score_field(&locked, lines, weights, danger_line), locked));
         }
     }
-    lines
+    if candidates.is_empty() {
+        return 0.0;
+    }
+
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    if rest.is_empty() {
+        return candidates[0].0;
+    }
+    candidates
+        .iter()
+        .take(LOOKAHEAD_BEAM)
+        .map(|(s, locked)| s + LOOKAHEAD_DISCOUNT * best_placement_score(locked, rest, weights, danger_line))
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+/// Copies the board, places the piece at (rotation, x, y), clears complete
+/// lines, and returns the resulting board together with the number of lines
+/// cleared.
+fn simulate_lock(field: &BitBoard, piece: &PieceDef, rotation: usize, x: i32, y: i32) -> (BitBoard, u32) {
+    let mut f = *field;
+    f.place(piece.mask(rotation), x, y);
+    let lines = f.clear_full_rows();
+    (f, lines)
 }
 
 // ---------------------------------------------------------------------------
 // Heuristic scoring
 // ---------------------------------------------------------------------------
 
-fn score_field(field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT], lines_cleared: u32) -> f64 {
-    let heights = column_heights(field);
+fn score_field(field: &BitBoard, lines_cleared: u32, weights: &Weights, danger_line: i32) -> f64 {
+    let heights = &field.column_heights()[..field.cols()];
     let agg_height: i32 = heights.iter().sum();
     let max_height = heights.iter().copied().max().unwrap_or(0);
-    let holes = count_holes(field, &heights) as i32;
-    let covered = count_covered_holes(field, &heights) as i32;
-    let bump = bumpiness(&heights);
-
-    // Steep extra penalty when the stack enters the danger zone (> 12 rows).
-    // Each additional row above 12 costs 3× extra to strongly discourage
-    // letting the board climb near the top.
-    let danger = if max_height > 12 {
-        (max_height - 12) as f64 * 3.0
-    } else {
-        0.0
-    };
-
-    -0.510066 * agg_height as f64
-        + 0.760666 * lines_cleared as f64
-        - 0.75    * holes as f64    // was -0.356630; holes are catastrophic
-        - 0.35    * covered as f64  // extra penalty for deeply buried holes
-        - 0.356630 * bump           // was -0.184483; high bumpiness blocks future pieces
-        - danger
-}
-
-/// Height of each interior column (index 0 = column 1 in the field).
-fn column_heights(field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT]) -> Vec<i32> {
-    let num_cols = FIELD_WIDTH - 2; // exclude left/right border columns
-    let mut heights = vec![0i32; num_cols];
-
-    for (i, col) in (1..FIELD_WIDTH - 1).enumerate() {
-        for row in 0..(FIELD_HEIGHT - 1) {
-            if field[row][col] != 0 {
-                heights[i] = (FIELD_HEIGHT - 1 - row) as i32;
-                break;
-            }
-        }
-    }
-    heights
-}
+    let holes = field.holes() as i32;
+    let covered = field.covered_holes() as i32;
+    let bump = bumpiness(heights);
 
-/// Count cells that are empty but have a filled cell somewhere above them.
-fn count_holes(field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT], heights: &[i32]) -> u32 {
-    let mut holes = 0u32;
-    for (i, &h) in heights.iter().enumerate() {
-        if h == 0 {
-            continue;
-        }
-        let col = i + 1; // actual field column
-        let top_row = (FIELD_HEIGHT as i32 - 1 - h) as usize;
-        for row in (top_row + 1)..(FIELD_HEIGHT - 1) {
-            if field[row][col] == 0 {
-                holes += 1;
-            }
-        }
-    }
-    holes
-}
+    // Steep extra penalty when the stack enters the danger zone near the
+    // top of the visible area, for every row above the line, to strongly
+    // discourage letting the board climb near the top.
+    let danger = (max_height - danger_line).max(0);
 
-/// Total burial depth: for each hole, count how many filled cells sit above it
-/// in the same column.  A hole buried under 3 blocks is far harder to clear
-/// than one buried under 1, so this adds a proportional penalty on top of the
-/// plain hole count.
-fn count_covered_holes(field: &[[u8; FIELD_WIDTH]; FIELD_HEIGHT], heights: &[i32]) -> u32 {
-    let mut total = 0u32;
-    for (i, &h) in heights.iter().enumerate() {
-        if h == 0 {
-            continue;
-        }
-        let col = i + 1;
-        let top_row = (FIELD_HEIGHT as i32 - 1 - h) as usize;
-        let mut cover = 0u32;
-        for row in top_row..(FIELD_HEIGHT - 1) {
-            if field[row][col] != 0 {
-                cover += 1;
-            } else {
-                // This cell is a hole; add the number of blocks overhead
-                total += cover;
-            }
-        }
-    }
-    total
+    weights.aggregate_height * agg_height as f64
+        + weights.lines_cleared * lines_cleared as f64
+        + weights.holes * holes as f64
+        + weights.covered_holes * covered as f64
+        + weights.bumpiness * bump
+        + weights.danger * danger as f64
 }
 
 /// Sum of absolute differences between adjacent column heights.
@@ -1,7 +1,9 @@
 use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
 use std::time::Duration;
 
-use crate::game::AudioEvent;
+use tetris::events::{GameEvent, Subscriber};
+use tetris::game::GameState;
+use tetris::spin::Spin;
 
 const SAMPLE_RATE: u32 = 44100;
 
@@ -203,51 +205,97 @@ impl AudioManager {
         self.sfx_sinks.push(sink);
     }
 
-    pub fn play_event(&mut self, event: &AudioEvent) {
+    fn play_lock(&mut self, hard_drop: bool, spin: Spin, lines: u32) {
+        if hard_drop {
+            self.play_notes(&[(280.0, 35.0, 0.22), (140.0, 55.0, 0.22)]);
+        }
+        if spin != Spin::None {
+            // Quick upward swish, longer the more lines it clears
+            let tail = 60.0 + 40.0 * lines as f32;
+            self.play_notes(&[
+                (587.33, 30.0, 0.20),
+                (880.00, 30.0, 0.20),
+                (1174.66, tail, 0.20),
+            ]);
+        } else if lines == 0 && !hard_drop {
+            self.play_notes(&[(130.0, 80.0, 0.20)]);
+        }
+    }
+
+    fn play_line_clear(&mut self, lines: usize) {
+        match lines {
+            1 => {
+                self.play_notes(&[(523.25, 80.0, 0.22), (659.25, 100.0, 0.22)]);
+            }
+            2 => {
+                self.play_notes(&[
+                    (523.25, 70.0, 0.22),
+                    (659.25, 70.0, 0.22),
+                    (783.99, 110.0, 0.22),
+                ]);
+            }
+            3 => {
+                self.play_notes(&[
+                    (523.25, 60.0, 0.22),
+                    (659.25, 60.0, 0.22),
+                    (783.99, 60.0, 0.22),
+                    (1046.50, 130.0, 0.22),
+                ]);
+            }
+            _ => {
+                // Tetris! Ascending fanfare
+                self.play_notes(&[
+                    (523.25,  55.0, 0.25),
+                    (659.25,  55.0, 0.25),
+                    (783.99,  55.0, 0.25),
+                    (1046.50, 55.0, 0.25),
+                    (1318.51, 200.0, 0.25),
+                ]);
+            }
+        }
+    }
+
+    fn play_perfect_clear(&mut self) {
+        // Two rising major arpeggios, the second an octave up
+        self.play_notes(&[
+            (523.25, 50.0, 0.24),
+            (659.25, 50.0, 0.24),
+            (783.99, 50.0, 0.24),
+            (1046.50, 50.0, 0.24),
+            (1318.51, 50.0, 0.24),
+            (1567.98, 50.0, 0.24),
+            (2093.00, 300.0, 0.24),
+        ]);
+    }
+
+    pub fn toggle_music(&mut self) {
+        self.music_enabled = !self.music_enabled;
+        self.music_sink.set_volume(if self.music_enabled { 1.0 } else { 0.0 });
+    }
+}
+
+impl Subscriber for AudioManager {
+    fn on_event(&mut self, _game: &GameState, event: &GameEvent) {
         match event {
-            AudioEvent::Move => {
+            GameEvent::Moved { dx, .. } if *dx != 0 => {
                 self.play_notes(&[(200.0, 25.0, 0.15)]);
             }
-            AudioEvent::Rotate => {
+            GameEvent::Rotated { .. } => {
                 self.play_notes(&[(330.0, 30.0, 0.18), (440.0, 30.0, 0.18)]);
             }
-            AudioEvent::Lock => {
-                self.play_notes(&[(130.0, 80.0, 0.20)]);
+            GameEvent::Hold { .. } => {
+                self.play_notes(&[(392.0, 35.0, 0.16), (294.0, 45.0, 0.16)]);
             }
-            AudioEvent::HardDrop => {
-                self.play_notes(&[(280.0, 35.0, 0.22), (140.0, 55.0, 0.22)]);
+            GameEvent::Locked { placement } => {
+                self.play_lock(placement.hard_drop, placement.spin, placement.lines);
             }
-            AudioEvent::LineClear(n) => match n {
-                1 => {
-                    self.play_notes(&[(523.25, 80.0, 0.22), (659.25, 100.0, 0.22)]);
-                }
-                2 => {
-                    self.play_notes(&[
-                        (523.25, 70.0, 0.22),
-                        (659.25, 70.0, 0.22),
-                        (783.99, 110.0, 0.22),
-                    ]);
+            GameEvent::LinesCleared { rows, kind } => {
+                self.play_line_clear(rows.len());
+                if kind.perfect_clear {
+                    self.play_perfect_clear();
                 }
-                3 => {
-                    self.play_notes(&[
-                        (523.25, 60.0, 0.22),
-                        (659.25, 60.0, 0.22),
-                        (783.99, 60.0, 0.22),
-                        (1046.50, 130.0, 0.22),
-                    ]);
-                }
-                _ => {
-                    // Tetris! Ascending fanfare
-                    self.play_notes(&[
-                        (523.25,  55.0, 0.25),
-                        (659.25,  55.0, 0.25),
-                        (783.99,  55.0, 0.25),
-                        (1046.50, 55.0, 0.25),
-                        (1318.51, 200.0, 0.25),
-                    ]);
-                }
-            },
-            AudioEvent::GameOver => {
+            }
+            GameEvent::GameOver { .. } => {
                 self.play_notes(&[
                     (440.00, 100.0, 0.20),
                     (370.00, 100.0, 0.20),
@@ -255,11 +303,7 @@ impl AudioManager {
                     (261.63, 350.0, 0.20),
                 ]);
             }
+            _ => {}
         }
     }
-
-    pub fn toggle_music(&mut self) {
-        self.music_enabled = !self.music_enabled;
-        self.music_sink.set_volume(if self.music_enabled { 1.0 } else { 0.0 });
-    }
 }
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+
+use crate::field::BoardSize;
+
+/// Most rows a board can have: the tallest board plus the deepest buffer.
+pub const MAX_ROWS: usize = BoardSize::MAX_HEIGHT + BoardSize::MAX_BUFFER;
+
+/// One rotation state of a piece as row bitmasks, precomputed so collision
+/// tests work a row at a time instead of a cell at a time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceMask {
+    /// Leftmost and rightmost cell offsets from the piece origin.
+    min_x: i32,
+    max_x: i32,
+    /// (y offset, cells) for each occupied row; bit 0 is column `min_x`.
+    rows: Vec<(i32, u32)>,
+}
+
+impl PieceMask {
+    pub fn new(cells: &[(i32, i32)]) -> Self {
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let max_x = cells.iter().map(|c| c.0).max().unwrap_or(0);
+        let mut rows: Vec<(i32, u32)> = Vec::new();
+        for &(x, y) in cells {
+            let bit = 1u32 << (x - min_x);
+            match rows.iter_mut().find(|r| r.0 == y) {
+                Some(row) => row.1 |= bit,
+                None => rows.push((y, bit)),
+            }
+        }
+        rows.sort_unstable_by_key(|r| r.0);
+        PieceMask { min_x, max_x, rows }
+    }
+}
+
+/// Occupancy of the playfield as one `u16` per row, bit `x - 1` set when
+/// column `x` is filled.  Coordinates match `Field`: column 1 is the first
+/// playable one (0 and `cols + 1` are the walls) and row 0 is the top of the
+/// buffer zone.  Walls, floor and the space above row 0 all count as filled.
+///
+/// The rows live in a fixed array, so copying a board (as the AI does for
+/// every placement it tries) never allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "SavedBoard", try_from = "SavedBoard")]
+pub struct BitBoard {
+    cols: usize,
+    height: usize,
+    rows: [u16; MAX_ROWS],
+}
+
+/// `BitBoard` as saved: only the rows in use.
+#[derive(Serialize, Deserialize)]
+struct SavedBoard {
+    cols: usize,
+    rows: Vec<u16>,
+}
+
+impl From<BitBoard> for SavedBoard {
+    fn from(board: BitBoard) -> Self {
+        SavedBoard { cols: board.cols, rows: board.rows[..board.height].to_vec() }
+    }
+}
+
+impl TryFrom<SavedBoard> for BitBoard {
+    type Error = String;
+
+    fn try_from(saved: SavedBoard) -> Result<Self, Self::Error> {
+        if saved.cols > BoardSize::MAX_WIDTH || saved.rows.len() > MAX_ROWS {
+            return Err(format!("board of {}x{} is too large", saved.cols, saved.rows.len()));
+        }
+        let mut board = BitBoard::new(saved.cols, saved.rows.len());
+        board.rows[..saved.rows.len()].copy_from_slice(&saved.rows);
+        Ok(board)
+    }
+}
+
+impl BitBoard {
+    /// An empty board `cols` wide and `height` rows tall (floor excluded).
+    pub fn new(cols: usize, height: usize) -> Self {
+        BitBoard { cols, height: height.min(MAX_ROWS), rows: [0; MAX_ROWS] }
+    }
+
+    /// Playable columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Rows above the floor.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Mask with a bit for every playable column.
+    fn full_row(&self) -> u16 {
+        (((1u32 << self.cols) - 1) & 0xffff) as u16
+    }
+
+    /// True if (x, y) is a wall, the floor, off the board or filled.
+    pub fn is_filled(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.cols as i32 || y < 0 || y >= self.height as i32 {
+            return true;
+        }
+        self.rows[y as usize] & (1 << (x - 1)) != 0
+    }
+
+    pub fn set(&mut self, x: i32, y: i32) {
+        if x >= 1 && x <= self.cols as i32 && y >= 0 && y < self.height as i32 {
+            self.rows[y as usize] |= 1 << (x - 1);
+        }
+    }
+
+    /// How far `mask`'s bits must be shifted to line up with the board at
+    /// column `x`, or `None` if the piece would stick out through a wall.
+    fn offset(&self, mask: &PieceMask, x: i32) -> Option<u32> {
+        let left = x + mask.min_x;
+        if left < 1 || x + mask.max_x > self.cols as i32 {
+            return None;
+        }
+        Some((left - 1) as u32)
+    }
+
+    /// Whether `mask` fits with its origin at (x, y).
+    pub fn fits(&self, mask: &PieceMask, x: i32, y: i32) -> bool {
+        let Some(shift) = self.offset(mask, x) else {
+            return false;
+        };
+        mask.rows.iter().all(|&(dy, bits)| {
+            let row = y + dy;
+            row >= 0 && row < self.height as i32 && self.rows[row as usize] as u32 & (bits << shift) == 0
+        })
+    }
+
+    /// How many rows `mask` can fall from (x, y) before it lands.  The
+    /// piece must fit at (x, y).
+    pub fn drop_distance(&self, mask: &PieceMask, x: i32, y: i32) -> i32 {
+        let Some(shift) = self.offset(mask, x) else {
+            return 0;
+        };
+        let mut distance = 0;
+        loop {
+            let blocked = mask.rows.iter().any(|&(dy, bits)| {
+                let row = y + distance + 1 + dy;
+                row >= self.height as i32 || (row >= 0 && self.rows[row as usize] as u32 & (bits << shift) != 0)
+            });
+            if blocked {
+                return distance;
+            }
+            distance += 1;
+        }
+    }
+
+    /// Fill the cells of `mask` at (x, y); cells off the board are dropped.
+    pub fn place(&mut self, mask: &PieceMask, x: i32, y: i32) {
+        let left = x + mask.min_x;
+        for &(dy, bits) in &mask.rows {
+            let row = y + dy;
+            if row < 0 || row >= self.height as i32 {
+                continue;
+            }
+            let shifted = if left >= 1 { (bits as u64) << (left - 1) } else { (bits as u64) >> (1 - left) };
+            self.rows[row as usize] |= (shifted & self.full_row() as u64) as u16;
+        }
+    }
+
+    pub fn is_row_full(&self, y: usize) -> bool {
+        self.rows[y] == self.full_row()
+    }
+
+    /// Remove row `y`, shifting everything above it down by one and
+    /// opening an empty row at the top.
+    pub fn remove_row(&mut self, y: usize) {
+        self.rows.copy_within(0..y, 1);
+        self.rows[0] = 0;
+    }
+
+    /// Remove every full row in one pass; returns how many there were.
+    pub fn clear_full_rows(&mut self) -> u32 {
+        let full = self.full_row();
+        let mut write = self.height;
+        for read in (0..self.height).rev() {
+            if self.rows[read] != full {
+                write -= 1;
+                self.rows[write] = self.rows[read];
+            }
+        }
+        // One empty row opens at the top for each row removed
+        self.rows[..write].fill(0);
+        write as u32
+    }
+
+    /// Stack height of each column (index 0 = column 1); entries past
+    /// `cols()` are zero.
+    pub fn column_heights(&self) -> [i32; BoardSize::MAX_WIDTH] {
+        let mut heights = [0; BoardSize::MAX_WIDTH];
+        let mut seen = 0u16;
+        for (y, &row) in self.rows[..self.height].iter().enumerate() {
+            let mut new = row & !seen;
+            while new != 0 {
+                let col = new.trailing_zeros() as usize;
+                heights[col] = (self.height - y) as i32;
+                new &= new - 1;
+            }
+            seen |= row;
+            if seen == self.full_row() {
+                break;
+            }
+        }
+        heights
+    }
+
+    /// Empty cells with a filled cell somewhere above them.
+    pub fn holes(&self) -> u32 {
+        let mut seen = 0u16;
+        let mut holes = 0;
+        for &row in &self.rows[..self.height] {
+            holes += (seen & !row).count_ones();
+            seen |= row;
+        }
+        holes
+    }
+
+    /// Total burial depth: for each hole, how many filled cells sit above it
+    /// in the same column.
+    pub fn covered_holes(&self) -> u32 {
+        let mut depth = [0u32; BoardSize::MAX_WIDTH];
+        let mut seen = 0u16;
+        let mut total = 0;
+        for &row in &self.rows[..self.height] {
+            let mut holes = seen & !row;
+            while holes != 0 {
+                total += depth[holes.trailing_zeros() as usize];
+                holes &= holes - 1;
+            }
+            let mut filled = row;
+            while filled != 0 {
+                depth[filled.trailing_zeros() as usize] += 1;
+                filled &= filled - 1;
+            }
+            seen |= row;
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    use super::*;
+    use crate::pieces::PieceSet;
+
+    /// The same board as a plain grid of cells, with every operation done
+    /// the obvious way, one cell at a time.
+    #[derive(Clone)]
+    struct Grid {
+        cols: usize,
+        cells: Vec<Vec<bool>>,
+    }
+
+    impl Grid {
+        fn height(&self) -> usize {
+            self.cells.len()
+        }
+
+        fn filled(&self, x: i32, y: i32) -> bool {
+            x < 1 || x > self.cols as i32 || y < 0 || y >= self.height() as i32 || self.cells[y as usize][x as usize - 1]
+        }
+
+        fn fits(&self, cells: &[(i32, i32)], x: i32, y: i32) -> bool {
+            cells.iter().all(|&(cx, cy)| !self.filled(x + cx, y + cy))
+        }
+
+        fn drop_distance(&self, cells: &[(i32, i32)], x: i32, y: i32) -> i32 {
+            let mut distance = 0;
+            while self.fits(cells, x, y + distance + 1) {
+                distance += 1;
+            }
+            distance
+        }
+
+        fn place(&mut self, cells: &[(i32, i32)], x: i32, y: i32) {
+            for &(cx, cy) in cells {
+                let (fx, fy) = (x + cx, y + cy);
+                if fx >= 1 && fx <= self.cols as i32 && fy >= 0 && fy < self.height() as i32 {
+                    self.cells[fy as usize][fx as usize - 1] = true;
+                }
+            }
+        }
+
+        fn clear_full_rows(&mut self) -> u32 {
+            let before = self.height();
+            self.cells.retain(|row| !row.iter().all(|&c| c));
+            let cleared = before - self.height();
+            for _ in 0..cleared {
+                self.cells.insert(0, vec![false; self.cols]);
+            }
+            cleared as u32
+        }
+
+        fn column_height(&self, col: usize) -> i32 {
+            match (0..self.height()).find(|&y| self.cells[y][col]) {
+                Some(top) => (self.height() - top) as i32,
+                None => 0,
+            }
+        }
+
+        /// Filled cells above each empty cell, for the empty cells that have
+        /// any.
+        fn burial_depths(&self) -> Vec<u32> {
+            let mut depths = Vec::new();
+            for col in 0..self.cols {
+                let mut above = 0;
+                for row in &self.cells {
+                    if row[col] {
+                        above += 1;
+                    } else if above > 0 {
+                        depths.push(above);
+                    }
+                }
+            }
+            depths
+        }
+
+        fn to_board(&self) -> BitBoard {
+            let mut board = BitBoard::new(self.cols, self.height());
+            for (y, row) in self.cells.iter().enumerate() {
+                for (x, &filled) in row.iter().enumerate() {
+                    if filled {
+                        board.set(x as i32 + 1, y as i32);
+                    }
+                }
+            }
+            board
+        }
+    }
+
+    /// A random stack, denser towards the floor, with some full rows and
+    /// the odd cell right at the top.
+    fn random_grid(rng: &mut Pcg64, cols: usize, height: usize) -> Grid {
+        let mut cells = vec![vec![false; cols]; height];
+        let stack = rng.gen_range(0..=height);
+        for (y, row) in cells.iter_mut().enumerate() {
+            if rng.gen_bool(0.1) {
+                row.fill(true);
+            } else if y >= height - stack {
+                row.iter_mut().for_each(|c| *c = rng.gen_bool(0.6));
+            }
+        }
+        if rng.gen_bool(0.2) {
+            cells[0][rng.gen_range(0..cols)] = true;
+        }
+        Grid { cols, cells }
+    }
+
+    /// Board shapes to try: the narrowest, the default and the widest, with
+    /// and without a buffer zone.
+    const SIZES: [(usize, usize); 5] = [(4, 4), (4, 24), (10, 40), (16, 8), (16, MAX_ROWS)];
+
+    fn check_boards(mut check: impl FnMut(&Grid, &BitBoard)) {
+        let mut rng = Pcg64::seed_from_u64(18);
+        for (cols, height) in SIZES {
+            for _ in 0..40 {
+                let grid = random_grid(&mut rng, cols, height);
+                check(&grid, &grid.to_board());
+            }
+        }
+    }
+
+    fn pieces() -> Vec<Vec<(i32, i32)>> {
+        [PieceSet::tetrominoes(), PieceSet::pentominoes()]
+            .iter()
+            .flat_map(|set| (0..set.len()).map(|p| set.get(p).clone()).collect::<Vec<_>>())
+            .flat_map(|def| (0..4).map(move |r| def.cells(r).to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn is_filled_matches_grid_including_walls_and_floor() {
+        check_boards(|grid, board| {
+            for y in -2..grid.height() as i32 + 2 {
+                for x in -2..grid.cols as i32 + 3 {
+                    assert_eq!(board.is_filled(x, y), grid.filled(x, y), "({}, {})", x, y);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn fits_and_drop_distance_match_grid() {
+        let pieces = pieces();
+        let mut rng = Pcg64::seed_from_u64(1);
+        check_boards(|grid, board| {
+            for cells in &pieces {
+                let mask = PieceMask::new(cells);
+                for x in -5..grid.cols as i32 + 3 {
+                    // Rows around the buffer edge at the top, and a random one
+                    let ys = [-3, -1, 0, 1, rng.gen_range(0..grid.height() as i32)];
+                    for y in ys {
+                        let fits = grid.fits(cells, x, y);
+                        assert_eq!(board.fits(&mask, x, y), fits, "{:?} at ({}, {})", cells, x, y);
+                        if fits {
+                            assert_eq!(board.drop_distance(&mask, x, y), grid.drop_distance(cells, x, y));
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn place_matches_grid_and_drops_cells_off_the_board() {
+        let pieces = pieces();
+        let mut rng = Pcg64::seed_from_u64(2);
+        check_boards(|grid, board| {
+            for _ in 0..20 {
+                let cells = &pieces[rng.gen_range(0..pieces.len())];
+                let x = rng.gen_range(-4..grid.cols as i32 + 2);
+                let y = rng.gen_range(-4..grid.height() as i32 + 2);
+                let (mut grid, mut board) = (grid.clone(), *board);
+                grid.place(cells, x, y);
+                board.place(&PieceMask::new(cells), x, y);
+                assert_eq!(board, grid.to_board(), "{:?} at ({}, {})", cells, x, y);
+            }
+        });
+    }
+
+    #[test]
+    fn clearing_rows_matches_grid() {
+        check_boards(|grid, board| {
+            for y in 0..grid.height() {
+                assert_eq!(board.is_row_full(y), grid.cells[y].iter().all(|&c| c));
+            }
+
+            let (mut cleared_grid, mut cleared) = (grid.clone(), *board);
+            assert_eq!(cleared.clear_full_rows(), cleared_grid.clear_full_rows());
+            assert_eq!(cleared, cleared_grid.to_board());
+
+            // Row by row, as the field does after the line-clear delay
+            let mut removed = *board;
+            for y in 0..grid.height() {
+                if removed.is_row_full(y) {
+                    removed.remove_row(y);
+                }
+            }
+            assert_eq!(removed, cleared);
+        });
+    }
+
+    #[test]
+    fn heights_and_holes_match_grid() {
+        check_boards(|grid, board| {
+            for (col, &height) in board.column_heights().iter().enumerate() {
+                let expected = if col < grid.cols { grid.column_height(col) } else { 0 };
+                assert_eq!(height, expected, "column {}", col + 1);
+            }
+            let depths = grid.burial_depths();
+            assert_eq!(board.holes(), depths.len() as u32);
+            assert_eq!(board.covered_holes(), depths.iter().sum::<u32>());
+        });
+    }
+
+    #[test]
+    fn saved_board_round_trips() {
+        check_boards(|_, board| {
+            let json = serde_json::to_string(board).unwrap();
+            assert_eq!(serde_json::from_str::<BitBoard>(&json).unwrap(), *board);
+        });
+        let too_wide = format!(r#"{{"cols":{},"rows":[]}}"#, BoardSize::MAX_WIDTH + 1);
+        assert!(serde_json::from_str::<BitBoard>(&too_wide).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use crate::game::{Command, GameState, TopOut};
+use crate::rotation::RotateDir;
+use crate::spin::Spin;
+
+/// Where a piece locked and what it did there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub piece: usize,
+    pub rotation: usize,
+    pub x: i32,
+    pub y: i32,
+    pub spin: Spin,
+    /// Rows the piece completed.
+    pub lines: u32,
+    /// It was hard-dropped rather than left to lock on its own.
+    pub hard_drop: bool,
+}
+
+/// What a line clear counted as, beyond the number of rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearKind {
+    pub spin: Spin,
+    /// The clear left the board empty.
+    pub perfect_clear: bool,
+    /// The clear was difficult and continued a back-to-back chain.
+    pub back_to_back: bool,
+}
+
+/// Something that happened in the game.  `GameState::step` returns them in
+/// order; front ends pass them on to their `Subscriber`s with `dispatch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A command reached the running game, at `time` on the game clock
+    /// (pauses excluded).  Replays are rebuilt from these.
+    Input { command: Command, time: Duration },
+    PieceSpawned { piece: usize },
+    /// The player shifted or soft-dropped the active piece (gravity is not
+    /// reported).
+    Moved { dx: i32, dy: i32 },
+    Rotated { dir: RotateDir, kick: usize },
+    /// The active piece went into the hold slot.
+    Hold { piece: usize },
+    Locked { placement: Placement },
+    /// Rows completed by the lock just reported, top to bottom in the
+    /// order they were found.  They are wiped during the line-clear delay.
+    LinesCleared { rows: Vec<usize>, kind: ClearKind },
+    LevelUp { level: u32 },
+    GameOver { reason: TopOut },
+}
+
+/// A consumer of the event stream: audio, on-screen effects, logs,
+/// recorders.
+pub trait Subscriber {
+    /// Called once per event, after the step that produced it; `game` is
+    /// the state at the end of that step.
+    fn on_event(&mut self, game: &GameState, event: &GameEvent);
+}
+
+/// Hand each of `events` to every subscriber in turn.
+pub fn dispatch(game: &GameState, events: &[GameEvent], subscribers: &mut [&mut dyn Subscriber]) {
+    for event in events {
+        for subscriber in subscribers.iter_mut() {
+            subscriber.on_event(game, event);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::ops::Index;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::BitBoard;
+
+/// Playable size of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSize {
+    /// Playable columns.
+    pub width: usize,
+    /// Visible rows.
+    pub height: usize,
+    /// Hidden rows above the visible area, where pieces spawn.
+    pub buffer: usize,
+}
+
+impl BoardSize {
+    pub const MIN_WIDTH: usize = 4;
+    pub const MAX_WIDTH: usize = 16;
+    pub const MIN_HEIGHT: usize = 4;
+    pub const MAX_HEIGHT: usize = 40;
+    pub const MAX_BUFFER: usize = 40;
+
+    /// Check the size is within the limits above.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(Self::MIN_WIDTH..=Self::MAX_WIDTH).contains(&self.width) {
+            return Err(format!("board width must be {}-{}", Self::MIN_WIDTH, Self::MAX_WIDTH));
+        }
+        if !(Self::MIN_HEIGHT..=Self::MAX_HEIGHT).contains(&self.height) {
+            return Err(format!("board height must be {}-{}", Self::MIN_HEIGHT, Self::MAX_HEIGHT));
+        }
+        if self.buffer > Self::MAX_BUFFER {
+            return Err(format!("board buffer must be 0-{}", Self::MAX_BUFFER));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BoardSize {
+    /// The guideline 10×20 board with a 20-row buffer zone.
+    fn default() -> Self {
+        BoardSize { width: 10, height: 20, buffer: 20 }
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl FromStr for BoardSize {
+    type Err = String;
+
+    /// `WIDTHxHEIGHT`, e.g. `10x20`.  The buffer keeps its default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (w, h) = s
+            .split_once('x')
+            .ok_or_else(|| format!("invalid board size '{}' (expected WIDTHxHEIGHT, e.g. 10x20)", s))?;
+        let width: usize = w.parse().map_err(|_| format!("invalid board width '{}'", w))?;
+        let height: usize = h.parse().map_err(|_| format!("invalid board height '{}'", h))?;
+        let size = BoardSize { width, height, ..BoardSize::default() };
+        size.validate()?;
+        Ok(size)
+    }
+}
+
+/// The playfield grid: occupancy as a `BitBoard` for collision tests, plus
+/// a color layer for drawing.  The color layer includes a wall column on
+/// each side and a floor row (value 9), stored row-major; `field[y][x]`
+/// reads it like the fixed arrays it replaces, with row 0 the top of the
+/// buffer zone.  Writes go through `place`, `mark_row` and `remove_row` so
+/// the two layers stay in step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    width: usize,
+    height: usize,
+    bits: BitBoard,
+    colors: Vec<u8>,
+}
+
+impl Field {
+    /// An empty field for `size`: its buffer plus visible rows, walled and
+    /// floored.
+    pub fn new(size: BoardSize) -> Self {
+        let width = size.width + 2;
+        let height = size.buffer + size.height + 1;
+        let bits = BitBoard::new(size.width, height - 1);
+        let mut field = Field { width, height, bits, colors: vec![0; width * height] };
+        for y in 0..height - 1 {
+            field.reset_row(y);
+        }
+        field.row_mut(height - 1).fill(9);
+        field
+    }
+
+    /// Check a field read back from a file has the layout `new` gives a
+    /// board of `size`.
+    pub fn validate(&self, size: BoardSize) -> Result<(), String> {
+        let consistent = self.width == size.width + 2
+            && self.height == size.buffer + size.height + 1
+            && self.colors.len() == self.width * self.height
+            && self.bits.cols() == size.width
+            && self.bits.height() == self.height - 1;
+        if !consistent {
+            return Err(format!("field doesn't match a {} board", size));
+        }
+        Ok(())
+    }
+
+    /// Total columns, walls included.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Total rows, floor included.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The occupancy layer.
+    pub fn bits(&self) -> &BitBoard {
+        &self.bits
+    }
+
+    /// True if (x, y) is off the grid or holds anything (walls included).
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.bits.is_filled(x, y)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.colors.chunks(self.width)
+    }
+
+    /// Fill `cells` offset by (x, y) with `color`; cells off the board are
+    /// dropped.
+    pub fn place(&mut self, cells: &[(i32, i32)], color: u8, x: i32, y: i32) {
+        for &(px, py) in cells {
+            let (fx, fy) = (x + px, y + py);
+            if fx >= 1 && fx < self.width as i32 - 1 && fy >= 0 && fy < self.height as i32 - 1 {
+                self.bits.set(fx, fy);
+                self.row_mut(fy as usize)[fx as usize] = color;
+            }
+        }
+    }
+
+    pub fn is_row_full(&self, y: usize) -> bool {
+        y < self.bits.height() && self.bits.is_row_full(y)
+    }
+
+    /// Recolor the playable cells of row `y` (e.g. to flag it for clearing)
+    /// without changing what is filled.
+    pub fn mark_row(&mut self, y: usize, color: u8) {
+        let width = self.width;
+        self.row_mut(y)[1..width - 1].fill(color);
+    }
+
+    /// Remove row `y`, shifting everything above it down by one and
+    /// opening an empty row at the top.
+    pub fn remove_row(&mut self, y: usize) {
+        self.bits.remove_row(y);
+        self.colors.copy_within(0..y * self.width, self.width);
+        self.reset_row(0);
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u8] {
+        &mut self.colors[y * self.width..(y + 1) * self.width]
+    }
+
+    fn reset_row(&mut self, y: usize) {
+        let width = self.width;
+        let row = self.row_mut(y);
+        row.fill(0);
+        row[0] = 9;
+        row[width - 1] = 9;
+    }
+}
+
+impl Index<usize> for Field {
+    type Output = [u8];
+
+    fn index(&self, y: usize) -> &[u8] {
+        &self.colors[y * self.width..(y + 1) * self.width]
+    }
+}
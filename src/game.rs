@@ -1,32 +1,400 @@
-use rand::Rng;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{ClearKind, GameEvent, Placement};
+use crate::field::{BoardSize, Field};
+use crate::gravity::GravityCurve;
+use crate::pieces::PieceSet;
+use crate::rotation::{RotateDir, RotationSystem};
+use crate::scoring::{LockResult, Scorer, ScoringRules};
+use crate::spin::{self, Spin};
+use crate::stats::Stats;
 use std::collections::VecDeque;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::time::Instant;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Lines needed to advance one level.
+const LINES_PER_LEVEL: u32 = 10;
+/// Locks practice mode can undo.
+pub const UNDO_LIMIT: usize = 100;
 
-pub const FIELD_WIDTH: usize = 12;
-pub const FIELD_HEIGHT: usize = 18;
+/// A player action.  Front ends and bots drive the game only through these
+/// (see `GameState::apply`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveLeft,
+    MoveRight,
+    /// Move down one row.
+    SoftDrop,
+    /// Soft drop straight onto the stack without locking.
+    SonicDrop,
+    /// Drop onto the stack and lock at once.
+    HardDrop,
+    Rotate(RotateDir),
+    Hold,
+    /// Pause or resume.
+    TogglePause,
+    /// Take back the last lock (practice mode).
+    Undo,
+    /// Put back a lock taken back by `Undo`.
+    Redo,
+}
 
-#[derive(Debug, Clone)]
-pub enum AudioEvent {
+/// The last thing that successfully moved the active piece.  A spin only
+/// counts if the piece was rotated into place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LastAction {
     Move,
-    Rotate,
+    /// Rotation, with the index of the kick test that succeeded.
+    Rotate { kick: usize },
+}
+
+/// What the game is doing: a piece in play, or one of the delays between
+/// pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    Falling,
+    /// Completed rows are being wiped; `elapsed` into the line-clear delay.
+    LineClear { elapsed: Duration },
+    /// Entry delay (ARE) before the next piece appears.
+    Entry { elapsed: Duration },
+}
+
+/// Why the game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TopOut {
+    /// A new piece spawned overlapping the stack.
+    Block,
+    /// A piece locked entirely inside the hidden buffer zone.
     Lock,
-    HardDrop,
-    LineClear(u32),
-    GameOver,
+    /// A piece locked with part of it inside the buffer zone (optional rule).
+    PartialLock,
+}
+
+impl TopOut {
+    /// One-line explanation for the game-over screen and log.
+    pub fn description(&self) -> &'static str {
+        match self {
+            TopOut::Block => "no room for the next piece",
+            TopOut::Lock => "piece locked above the board",
+            TopOut::PartialLock => "piece locked partly above the board",
+        }
+    }
+}
+
+impl fmt::Display for TopOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TopOut::Block => "Block out",
+            TopOut::Lock => "Lock out",
+            TopOut::PartialLock => "Partial lock out",
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Piece generators
+// ---------------------------------------------------------------------------
+
+/// Source of the piece sequence.  Implementations only decide *which* piece
+/// comes next; the RNG is owned by the caller so every generator draws from
+/// the same stream.
+pub trait PieceGenerator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize;
+}
+
+/// Uniform random pick with no memory (the original behaviour).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomGenerator {
+    count: usize,
+}
+
+impl PieceGenerator for RandomGenerator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize {
+        rng.gen_range(0..self.count)
+    }
+}
+
+/// Modern guideline randomizer: deal every piece of the set once in a
+/// shuffled bag, then refill.  With tetrominoes this guarantees at most 12
+/// pieces between two I pieces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BagGenerator {
+    count: usize,
+    bag: Vec<usize>,
+}
+
+impl BagGenerator {
+    pub fn new(count: usize) -> Self {
+        BagGenerator { count, bag: Vec::with_capacity(count) }
+    }
+}
+
+impl PieceGenerator for BagGenerator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize {
+        if self.bag.is_empty() {
+            self.bag.extend(0..self.count);
+            // Fisher–Yates; pieces are popped from the back
+            for i in (1..self.bag.len()).rev() {
+                let j = rng.gen_range(0..=i);
+                self.bag.swap(i, j);
+            }
+        }
+        self.bag.pop().unwrap_or(0)
+    }
+}
+
+/// NES randomizer: roll a die with one extra dummy side; on the dummy side or
+/// a repeat of the previous piece, reroll once without the dummy and accept
+/// the result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NesGenerator {
+    count: usize,
+    last: Option<usize>,
+}
+
+impl NesGenerator {
+    pub fn new(count: usize) -> Self {
+        NesGenerator { count, last: None }
+    }
+}
+
+impl PieceGenerator for NesGenerator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize {
+        let mut piece = rng.gen_range(0..=self.count);
+        if piece == self.count || Some(piece) == self.last {
+            piece = rng.gen_range(0..self.count);
+        }
+        self.last = Some(piece);
+        piece
+    }
+}
+
+/// TGM randomizer: keep a history of the last 4 pieces and reroll up to 6
+/// times while the candidate is in it.  With tetrominoes the history starts
+/// as Z/S/Z/S and the first piece is never S, Z or O.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TgmGenerator {
+    count: usize,
+    history: VecDeque<usize>,
+    first_choices: Vec<usize>,
+}
+
+impl TgmGenerator {
+    const ROLLS: usize = 6;
+    const HISTORY: usize = 4;
+
+    pub fn new(count: usize, tetromino: bool) -> Self {
+        let (history, first_choices) = if tetromino {
+            // I, T, L, J
+            (VecDeque::from([2, 1, 2, 1]), vec![0, 4, 5, 6])
+        } else {
+            (VecDeque::new(), (0..count).collect())
+        };
+        // Only ever hand out pieces the set has
+        TgmGenerator {
+            count,
+            history: history.into_iter().filter(|&p| p < count).collect(),
+            first_choices: first_choices.into_iter().filter(|&p| p < count).collect(),
+        }
+    }
+}
+
+impl PieceGenerator for TgmGenerator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize {
+        let piece = if !self.first_choices.is_empty() {
+            let choices = std::mem::take(&mut self.first_choices);
+            choices[rng.gen_range(0..choices.len())]
+        } else {
+            let mut piece = rng.gen_range(0..self.count);
+            for _ in 1..Self::ROLLS {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = rng.gen_range(0..self.count);
+            }
+            piece
+        };
+        if self.history.len() >= Self::HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(piece);
+        piece
+    }
+}
+
+/// One of the built-in generators, with its state.  Unlike a boxed
+/// `PieceGenerator` it can be saved with the game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Generator {
+    Random(RandomGenerator),
+    Bag(BagGenerator),
+    Nes(NesGenerator),
+    Tgm(TgmGenerator),
 }
 
-// 7 tetrominoes as 16-char strings (4×4 grids), matching C++ shapes
-pub const TETROMINOES: [&str; 7] = [
-    "..X...X...X...X.", // I
-    "..X..XX...X.....", // S
-    ".X...XX...X.....", // Z
-    ".X...XX..X......", // O (actually the S-mirror; keeping C++ layout)
-    "..X..XX..X......", // T
-    ".X...X...XX.....", // L
-    "..X...X..XX.....", // J
-];
+impl Generator {
+    /// True if the generator is for a set of `count` pieces and only holds
+    /// pieces from it.
+    fn fits(&self, count: usize) -> bool {
+        let in_set = |p: &usize| *p < count;
+        match self {
+            Generator::Random(g) => g.count == count,
+            Generator::Bag(g) => g.count == count && g.bag.iter().all(in_set),
+            Generator::Nes(g) => g.count == count && g.last.iter().all(in_set),
+            Generator::Tgm(g) => g.count == count && g.history.iter().chain(&g.first_choices).all(in_set),
+        }
+    }
+}
+
+impl PieceGenerator for Generator {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> usize {
+        match self {
+            Generator::Random(g) => g.next_piece(rng),
+            Generator::Bag(g) => g.next_piece(rng),
+            Generator::Nes(g) => g.next_piece(rng),
+            Generator::Tgm(g) => g.next_piece(rng),
+        }
+    }
+}
+
+/// Selectable generator, as named on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorKind {
+    #[default]
+    Bag,
+    Nes,
+    Tgm,
+    Random,
+}
+
+impl GeneratorKind {
+    pub fn build(self, pieces: &PieceSet) -> Generator {
+        let count = pieces.len();
+        match self {
+            GeneratorKind::Bag => Generator::Bag(BagGenerator::new(count)),
+            GeneratorKind::Nes => Generator::Nes(NesGenerator::new(count)),
+            GeneratorKind::Tgm => Generator::Tgm(TgmGenerator::new(count, pieces.is_tetromino())),
+            GeneratorKind::Random => Generator::Random(RandomGenerator { count }),
+        }
+    }
+}
+
+impl fmt::Display for GeneratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GeneratorKind::Bag => "bag",
+            GeneratorKind::Nes => "nes",
+            GeneratorKind::Tgm => "tgm",
+            GeneratorKind::Random => "random",
+        })
+    }
+}
+
+impl FromStr for GeneratorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bag" | "7bag" => Ok(GeneratorKind::Bag),
+            "nes" => Ok(GeneratorKind::Nes),
+            "tgm" => Ok(GeneratorKind::Tgm),
+            "random" => Ok(GeneratorKind::Random),
+            _ => Err(format!("unknown generator '{}' (expected bag, nes, tgm or random)", s)),
+        }
+    }
+}
+
+/// Settings fixed for the lifetime of a game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub pieces: Arc<PieceSet>,
+    pub generator: GeneratorKind,
+    /// RNG seed; a random one is drawn when `None`.
+    pub seed: Option<u64>,
+    pub rotation: RotationSystem,
+    /// Number of upcoming pieces kept in the preview queue (1-6).
+    pub preview: usize,
+    /// How long a grounded piece may sit before locking (0 = lock on contact).
+    pub lock_delay_ms: u32,
+    /// Moves/rotations on the ground that restart the lock timer, per piece.
+    /// The count is re-armed whenever the piece reaches a new lowest row.
+    pub lock_resets: u32,
+    /// Entry delay (ARE) between a lock and the next piece appearing.
+    pub are_ms: u32,
+    /// How long completed rows are animated before they are removed.
+    pub line_clear_ms: u32,
+    pub gravity: GravityCurve,
+    pub start_level: u32,
+    pub scoring: ScoringRules,
+    /// Count any piece rotated into a spot where it can't move left, right
+    /// or up as a spin, not just the T.
+    pub all_spin: bool,
+    /// Playfield size, including the hidden buffer rows above it.
+    pub board: BoardSize,
+    /// End the game when any part of a piece locks in the buffer zone, as
+    /// classic games do, rather than only when all of it does.
+    pub partial_lock_out: bool,
+    /// Keep undo points so locks can be taken back with `Command::Undo`.
+    #[serde(default)]
+    pub practice: bool,
+}
+
+impl GameConfig {
+    pub const MAX_PREVIEW: usize = 6;
+    /// Highest starting level; play can still take the level beyond it.
+    pub const MAX_LEVEL: u32 = 99;
+
+    /// Check settings read back from a file are ones the game can run
+    /// with, as the command line ensures for its own.
+    pub fn validate(&self) -> Result<(), String> {
+        self.pieces.validate()?;
+        self.board.validate()?;
+        self.gravity.validate()?;
+        if !(1..=Self::MAX_PREVIEW).contains(&self.preview) {
+            return Err(format!("preview must be 1-{}", Self::MAX_PREVIEW));
+        }
+        if self.start_level > Self::MAX_LEVEL {
+            return Err(format!("level must be 0-{}", Self::MAX_LEVEL));
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            pieces: Arc::default(),
+            generator: GeneratorKind::default(),
+            seed: None,
+            rotation: RotationSystem::default(),
+            preview: 3,
+            lock_delay_ms: 500,
+            lock_resets: 15,
+            are_ms: 100,
+            line_clear_ms: 300,
+            gravity: GravityCurve::default(),
+            start_level: 1,
+            scoring: ScoringRules::default(),
+            all_spin: false,
+            board: BoardSize::default(),
+            partial_lock_out: false,
+            practice: false,
+        }
+    }
+}
+
+/// Field column for the left edge of a `size`-wide piece box so the piece
+/// spawns centred on a `cols`-wide board (rounding left, per the guideline).
+fn spawn_x(cols: usize, size: usize) -> i32 {
+    1 + (cols as i32 - size as i32) / 2
+}
 
 pub struct BoardStats {
     pub max_height: i32,
@@ -34,442 +402,698 @@ pub struct BoardStats {
     pub bumpiness: i32,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameState {
-    pub field: [[u8; FIELD_WIDTH]; FIELD_HEIGHT],
+    pub field: Field,
     pub current_piece: usize,
     pub current_rotation: usize,
     pub current_x: i32,
     pub current_y: i32,
-    pub next_piece: usize,
+    /// Upcoming pieces, front first; always `config.preview` long.
+    pub next_queue: VecDeque<usize>,
+    pub hold_piece: Option<usize>,
+    /// Set once the active piece has been swapped into the hold slot; cleared
+    /// when a piece locks.
+    pub hold_used: bool,
     pub score: u32,
+    /// Scoring rules with the combo and back-to-back state.
+    pub scorer: Scorer,
     pub piece_count: u32,
-    pub speed: u32,
-    pub speed_counter: u32,
+    /// Advances every `LINES_PER_LEVEL` lines; sets the gravity.
+    pub level: u32,
+    /// Time accumulated towards the next gravity step.
+    gravity_timer: Duration,
     pub lines_to_clear: Vec<usize>,
+    pub phase: Phase,
+    /// Rotation pressed during a delay, applied as the next piece spawns
+    /// (IRS).
+    buffered_rotation: Option<RotateDir>,
+    /// Hold pressed during a delay, applied as the next piece spawns (IHS).
+    buffered_hold: bool,
     pub game_over: bool,
+    /// Why the game ended, once it has.
+    pub top_out: Option<TopOut>,
     pub paused: bool,
-    pub ai_mode: bool,
-    pub ai_target_rotation: usize,
-    pub ai_target_x: i32,
-    // Analytics
-    pub lines_cleared: u32,
-    pub singles: u32,
-    pub doubles: u32,
-    pub triples: u32,
-    pub tetrises: u32,
-    pub lines_history: VecDeque<u8>, // lines cleared per last 20 pieces
-    pub start_time: Instant,
-    pub pending_sounds: Vec<AudioEvent>,
+    /// How long the active piece has been resting on the stack, or `None`
+    /// while it can still fall.
+    pub lock_timer: Option<Duration>,
+    lock_resets_used: u32,
+    /// Deepest row the active piece has reached; used to re-arm lock resets.
+    lowest_y: i32,
+    pub last_action: LastAction,
+    pub stats: Stats,
+    /// Events since the last `step`.
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+    /// Left out when serialized, as it would be repeated for every undo
+    /// point; saves store it once and put it back with `attach_config`.
+    #[serde(skip)]
+    pub config: GameConfig,
+    /// Seed actually used for this game (recorded so it can be replayed).
+    pub seed: u64,
+    rng: Pcg64,
+    generator: Generator,
+    /// Set once undo has been used; such games don't count for high scores.
+    #[serde(default)]
+    pub undo_used: bool,
+    /// Set by the front end once the AI has played part of the game; such
+    /// games are scored apart.
+    #[serde(default)]
+    pub ai_used: bool,
+    /// Kept in saves so undo carries on after a resume.
+    #[serde(default)]
+    history: History,
 }
 
-impl GameState {
-    pub fn new() -> Self {
-        let mut field = [[0u8; FIELD_WIDTH]; FIELD_HEIGHT];
+/// Practice-mode undo points: the state after each lock, oldest first,
+/// with the one play is continuing from at `cursor`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct History {
+    states: VecDeque<GameState>,
+    cursor: usize,
+}
 
-        // Set borders: left/right columns = 9, bottom row = 9
-        for y in 0..FIELD_HEIGHT {
-            field[y][0] = 9;
-            field[y][FIELD_WIDTH - 1] = 9;
-        }
-        for x in 0..FIELD_WIDTH {
-            field[FIELD_HEIGHT - 1][x] = 9;
-        }
+impl GameState {
+    pub fn new(config: GameConfig) -> Self {
+        let field = Field::new(config.board);
 
-        let mut rng = rand::thread_rng();
-        let current_piece = rng.gen_range(0..7);
-        let next_piece = rng.gen_range(0..7);
+        // Pcg64 rather than StdRng: its output is fixed across rand releases,
+        // so a logged seed keeps reproducing the same game.
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let mut generator = config.generator.build(&config.pieces);
+        let current_piece = generator.next_piece(&mut rng);
+        let preview = config.preview.clamp(1, GameConfig::MAX_PREVIEW);
+        let next_queue = (0..preview).map(|_| generator.next_piece(&mut rng)).collect();
 
-        let spawn_x = (FIELD_WIDTH as i32 / 2) - 2;
         let mut gs = GameState {
             field,
             current_piece,
             current_rotation: 0,
-            current_x: spawn_x,
+            current_x: 0,
             current_y: 0,
-            next_piece,
+            next_queue,
+            hold_piece: None,
+            hold_used: false,
             score: 0,
+            scorer: Scorer::new(config.scoring),
             piece_count: 0,
-            speed: 20,
-            speed_counter: 0,
+            level: config.start_level,
+            gravity_timer: Duration::ZERO,
             lines_to_clear: Vec::new(),
+            phase: Phase::Falling,
+            buffered_rotation: None,
+            buffered_hold: false,
             game_over: false,
+            top_out: None,
             paused: false,
-            ai_mode: false,
-            ai_target_rotation: 0,
-            ai_target_x: spawn_x,
-            lines_cleared: 0,
-            singles: 0,
-            doubles: 0,
-            triples: 0,
-            tetrises: 0,
-            lines_history: VecDeque::with_capacity(20),
-            start_time: Instant::now(),
-            pending_sounds: Vec::new(),
+            lock_timer: None,
+            lock_resets_used: 0,
+            lowest_y: 0,
+            last_action: LastAction::Move,
+            stats: Stats::default(),
+            events: Vec::new(),
+            config,
+            seed,
+            rng,
+            generator,
+            undo_used: false,
+            ai_used: false,
+            history: History::default(),
         };
 
-        // Check if initial piece fits (it should always fit at spawn)
-        if !gs.does_piece_fit(gs.current_piece, gs.current_rotation, gs.current_x, gs.current_y) {
-            gs.game_over = true;
-        }
-
+        gs.spawn_piece(current_piece);
+        gs.save_snapshot();
         gs
     }
 
-    /// Rotate index: convert (px, py, rotation) to tetromino string index
-    pub fn rotate(px: usize, py: usize, r: usize) -> usize {
-        match r % 4 {
-            0 => py * 4 + px,
-            1 => 12 + py - (px * 4),
-            2 => 15 - (py * 4) - px,
-            3 => 3 - py + (px * 4),
-            _ => unreachable!(),
+    /// Check a game read back from a file is consistent: valid settings, a
+    /// field of the configured size, a full preview queue, and only pieces
+    /// from the set.
+    pub fn validate(&self) -> Result<(), String> {
+        self.config.validate()?;
+        self.field.validate(self.config.board)?;
+        let count = self.config.pieces.len();
+        let in_set = |p: &usize| *p < count;
+        let pieces_ok = in_set(&self.current_piece)
+            && self.next_queue.iter().all(in_set)
+            && self.hold_piece.iter().all(in_set)
+            && self.generator.fits(count);
+        if !pieces_ok {
+            return Err("game holds pieces that aren't in its set".into());
+        }
+        if self.next_queue.len() != self.config.preview {
+            return Err("preview queue doesn't match the preview setting".into());
+        }
+        if self.current_rotation >= 4 || self.lines_to_clear.iter().any(|&row| row >= self.field.height() - 1) {
+            return Err("game state is out of range".into());
+        }
+        if !self.history.states.is_empty() && self.history.cursor >= self.history.states.len() {
+            return Err("undo history is out of range".into());
+        }
+        self.history.states.iter().try_for_each(GameState::validate)
+    }
+
+    /// Give a deserialized game, and each of its undo points, its settings.
+    pub(crate) fn attach_config(&mut self, config: GameConfig) {
+        for state in &mut self.history.states {
+            state.attach_config(config.clone());
         }
+        self.config = config;
     }
 
     pub fn does_piece_fit(&self, piece: usize, rotation: usize, pos_x: i32, pos_y: i32) -> bool {
-        let tetromino = TETROMINOES[piece];
-        for px in 0..4usize {
-            for py in 0..4usize {
-                let pi = Self::rotate(px, py, rotation);
-                let fi_x = pos_x + px as i32;
-                let fi_y = pos_y + py as i32;
-
-                if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                    // Out of bounds check
-                    if fi_x < 0 || fi_x >= FIELD_WIDTH as i32 || fi_y < 0 || fi_y >= FIELD_HEIGHT as i32 {
-                        return false;
-                    }
-                    // Collision with field
-                    if self.field[fi_y as usize][fi_x as usize] != 0 {
-                        return false;
-                    }
+        self.field.bits().fits(self.config.pieces.mask(piece, rotation), pos_x, pos_y)
+    }
+
+    /// Advance the game clock by `dt` and return everything that happened
+    /// since the last step, including the effects of commands applied in
+    /// between.  Meant to be called with a fixed `dt` so the game plays the
+    /// same whatever the frame rate.
+    pub fn step(&mut self, dt: Duration) -> Vec<GameEvent> {
+        self.update(dt);
+        std::mem::take(&mut self.events)
+    }
+
+    /// Carry out a player command.  Commands that can't apply right now
+    /// (blocked moves, or anything while paused or after game over) are
+    /// ignored; rotation and hold between pieces are buffered for the next
+    /// one.  Only commands that had an effect are reported as `Input`.
+    pub fn apply(&mut self, command: Command) {
+        let time = self.stats.elapsed;
+        let start = self.events.len();
+        let applied = match command {
+            Command::MoveLeft => self.shift(-1),
+            Command::MoveRight => self.shift(1),
+            Command::SoftDrop => self.move_down(),
+            Command::SonicDrop => self.sonic_drop(),
+            Command::HardDrop => self.hard_drop(),
+            Command::Rotate(dir) => self.rotate_piece(dir),
+            Command::Hold => self.hold(),
+            Command::TogglePause => {
+                if !self.game_over {
+                    self.paused = !self.paused;
                 }
+                // The game clock stops while paused, so replays don't need it
+                false
             }
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+        };
+        if applied {
+            // Ahead of whatever the command caused
+            self.events.insert(start, GameEvent::Input { command, time });
         }
-        true
     }
 
-    /// Called every 50ms tick
-    pub fn tick(&mut self) {
+    /// Run the delays between pieces, gravity and the lock delay for `dt`.
+    fn update(&mut self, dt: Duration) {
         if self.game_over || self.paused {
             return;
         }
+        self.stats.elapsed += dt;
 
-        // Process line clears (flash effect would be here; we clear immediately)
-        if !self.lines_to_clear.is_empty() {
-            self.clear_lines();
+        if self.phase != Phase::Falling {
+            self.advance_phase(dt);
             return;
         }
 
-        self.speed_counter += 1;
-        if self.speed_counter >= self.speed {
-            self.speed_counter = 0;
-            self.force_down();
+        match self.config.gravity.row_time(self.level) {
+            Some(row_time) => {
+                self.gravity_timer += dt;
+                while self.gravity_timer >= row_time {
+                    self.gravity_timer -= row_time;
+                    let grounded = self.is_grounded();
+                    self.step_down();
+                    if grounded {
+                        // A resting piece doesn't bank gravity; the lock delay takes over
+                        self.gravity_timer = Duration::ZERO;
+                        break;
+                    }
+                }
+            }
+            None => {
+                // 20G: the piece drops onto the stack as soon as it can
+                let drop_y = self.ghost_drop_y();
+                if drop_y > self.current_y {
+                    self.current_y = drop_y;
+                    self.last_action = LastAction::Move;
+                    self.note_descent();
+                }
+            }
         }
+
+        // Lock delay: count how long the piece has been resting
+        if !self.piece_active() {
+            return;
+        }
+        if self.is_grounded() {
+            let elapsed = self.lock_timer.unwrap_or_default() + dt;
+            if elapsed >= Duration::from_millis(self.config.lock_delay_ms as u64) {
+                self.lock_piece(false);
+            } else {
+                self.lock_timer = Some(elapsed);
+            }
+        } else {
+            self.lock_timer = None;
+        }
+    }
+
+    /// Run the delays between pieces: wipe completed rows, then wait out
+    /// ARE and spawn the next piece.  Zero-length delays pass straight
+    /// through.
+    fn advance_phase(&mut self, dt: Duration) {
+        match self.phase {
+            Phase::Falling => {}
+            Phase::LineClear { elapsed } => {
+                let elapsed = elapsed + dt;
+                if elapsed >= Duration::from_millis(self.config.line_clear_ms as u64) {
+                    self.clear_lines();
+                    self.phase = Phase::Entry { elapsed: Duration::ZERO };
+                    self.advance_phase(Duration::ZERO);
+                } else {
+                    self.phase = Phase::LineClear { elapsed };
+                }
+            }
+            Phase::Entry { elapsed } => {
+                let elapsed = elapsed + dt;
+                if elapsed >= Duration::from_millis(self.config.are_ms as u64) {
+                    self.spawn_next();
+                } else {
+                    self.phase = Phase::Entry { elapsed };
+                }
+            }
+        }
+    }
+
+    /// True while a piece is in play (not game over or between pieces).
+    pub fn piece_active(&self) -> bool {
+        !self.game_over && self.phase == Phase::Falling
+    }
+
+    /// How far the line-clear animation has got (0-1), if it is running.
+    pub fn line_clear_progress(&self) -> Option<f32> {
+        let Phase::LineClear { elapsed } = self.phase else {
+            return None;
+        };
+        Some((elapsed.as_secs_f32() * 1000.0 / self.config.line_clear_ms.max(1) as f32).min(1.0))
     }
 
-    fn force_down(&mut self) {
+    /// Move the active piece down one row if it fits.  A grounded piece is
+    /// left for the lock timer in `update`, unless lock delay is disabled.
+    fn step_down(&mut self) {
         if self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y + 1) {
             self.current_y += 1;
-        } else {
-            self.lock_piece();
+            self.last_action = LastAction::Move;
+            self.note_descent();
+        } else if self.config.lock_delay_ms == 0 {
+            self.lock_piece(false);
         }
     }
 
-    pub fn move_left(&mut self) {
-        if self.game_over || self.paused {
-            return;
+    fn is_grounded(&self) -> bool {
+        !self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y + 1)
+    }
+
+    /// Reaching a new lowest row re-arms the lock reset allowance.
+    fn note_descent(&mut self) {
+        if self.current_y > self.lowest_y {
+            self.lowest_y = self.current_y;
+            self.lock_resets_used = 0;
         }
-        if self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x - 1, self.current_y) {
-            self.current_x -= 1;
-            self.pending_sounds.push(AudioEvent::Move);
+    }
+
+    /// A successful move or rotation restarts a running lock timer, up to
+    /// `lock_resets` times per piece.
+    fn lock_reset(&mut self) {
+        if self.lock_timer.is_some() && self.lock_resets_used < self.config.lock_resets {
+            self.lock_timer = Some(Duration::ZERO);
+            self.lock_resets_used += 1;
         }
     }
 
-    pub fn move_right(&mut self) {
-        if self.game_over || self.paused {
-            return;
+    /// Fraction of the lock delay used up, for the on-board indicator.
+    pub fn lock_progress(&self) -> Option<f32> {
+        let elapsed = self.lock_timer?;
+        Some((elapsed.as_secs_f32() * 1000.0 / self.config.lock_delay_ms.max(1) as f32).min(1.0))
+    }
+
+    /// Move the active piece `dx` columns if it fits; true if it moved.
+    fn shift(&mut self, dx: i32) -> bool {
+        if self.paused || !self.piece_active() {
+            return false;
         }
-        if self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x + 1, self.current_y) {
-            self.current_x += 1;
-            self.pending_sounds.push(AudioEvent::Move);
+        if !self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x + dx, self.current_y) {
+            return false;
         }
+        self.current_x += dx;
+        self.last_action = LastAction::Move;
+        self.lock_reset();
+        self.emit(GameEvent::Moved { dx, dy: 0 });
+        true
     }
 
     /// Returns the Y position where the current piece would land (for ghost rendering).
     pub fn ghost_drop_y(&self) -> i32 {
-        let mut y = self.current_y;
-        while self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, y + 1) {
-            y += 1;
+        let mask = self.config.pieces.get(self.current_piece).mask(self.current_rotation);
+        self.current_y + self.field.bits().drop_distance(mask, self.current_x, self.current_y)
+    }
+
+    fn hard_drop(&mut self) -> bool {
+        if self.paused || !self.piece_active() {
+            return false;
+        }
+        let drop_y = self.ghost_drop_y();
+        if drop_y > self.current_y {
+            self.score = self.score.saturating_add(self.scorer.hard_drop((drop_y - self.current_y) as u32));
+            self.current_y = drop_y;
+            self.last_action = LastAction::Move;
         }
-        y
+        self.lock_piece(true);
+        true
     }
 
-    pub fn hard_drop(&mut self) {
-        if self.game_over || self.paused {
-            return;
+    /// Soft drop one row; true if the piece moved or, with no lock delay,
+    /// locked.
+    fn move_down(&mut self) -> bool {
+        if self.paused || !self.piece_active() {
+            return false;
         }
-        while self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y + 1) {
-            self.current_y += 1;
+        let (y, pieces) = (self.current_y, self.piece_count);
+        self.step_down();
+        if self.current_y > y {
+            self.score = self.score.saturating_add(self.scorer.soft_drop(1));
+            self.emit(GameEvent::Moved { dx: 0, dy: 1 });
         }
-        self.pending_sounds.push(AudioEvent::HardDrop);
-        self.lock_piece();
+        self.current_y > y || self.piece_count > pieces
     }
 
-    pub fn move_down(&mut self) {
-        if self.game_over || self.paused {
-            return;
+    /// Soft drop all the way down, scored like repeated soft drops; true if
+    /// the piece moved.
+    fn sonic_drop(&mut self) -> bool {
+        if self.paused || !self.piece_active() {
+            return false;
         }
-        if self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y + 1) {
-            self.current_y += 1;
-        } else {
-            self.lock_piece();
+        let drop_y = self.ghost_drop_y();
+        if drop_y <= self.current_y {
+            return false;
         }
+        let dy = drop_y - self.current_y;
+        self.score = self.score.saturating_add(self.scorer.soft_drop(dy as u32));
+        self.current_y = drop_y;
+        self.last_action = LastAction::Move;
+        self.note_descent();
+        self.emit(GameEvent::Moved { dx: 0, dy });
+        true
     }
 
-    pub fn rotate_piece(&mut self) {
+    /// Rotate the active piece, trying the configured rotation system's
+    /// kicks in order.  Between pieces the rotation is buffered for the next
+    /// one instead.  True if the piece turned or the rotation was buffered.
+    fn rotate_piece(&mut self, dir: RotateDir) -> bool {
         if self.game_over || self.paused {
-            return;
+            return false;
         }
-        let new_rotation = (self.current_rotation + 1) % 4;
-        if self.does_piece_fit(self.current_piece, new_rotation, self.current_x, self.current_y) {
-            self.current_rotation = new_rotation;
-            self.pending_sounds.push(AudioEvent::Rotate);
+        if !self.piece_active() {
+            self.buffered_rotation = Some(dir);
+            return true;
         }
+        let piece = self.current_piece;
+        let rotated = self.config.rotation.try_rotate(
+            self.config.pieces.get(piece).kicks,
+            self.current_rotation,
+            self.current_x,
+            self.current_y,
+            dir,
+            |r, x, y| self.does_piece_fit(piece, r, x, y),
+        );
+        let Some((rotation, x, y, kick)) = rotated else {
+            return false;
+        };
+        self.current_rotation = rotation;
+        self.current_x = x;
+        self.current_y = y;
+        self.last_action = LastAction::Rotate { kick };
+        self.note_descent();
+        self.lock_reset();
+        self.emit(GameEvent::Rotated { dir, kick });
+        true
     }
 
-    fn lock_piece(&mut self) {
-        let tetromino = TETROMINOES[self.current_piece];
+    /// Lock the active piece where it is; `hard_drop` tells whether it was
+    /// dropped there.
+    fn lock_piece(&mut self, hard_drop: bool) {
+        let spin = self.detect_spin();
+        let def = self.config.pieces.get(self.current_piece);
+        let cells = def.cells(self.current_rotation);
+        // Cells of the piece locking in the hidden buffer zone
+        let buffer = self.config.board.buffer as i32;
+        let hidden = cells.iter().filter(|&&(_, py)| self.current_y + py < buffer).count();
+        let lock_out = if hidden == cells.len() {
+            Some(TopOut::Lock)
+        } else if hidden > 0 && self.config.partial_lock_out {
+            Some(TopOut::PartialLock)
+        } else {
+            None
+        };
+
         // Write piece to field
-        for px in 0..4usize {
-            for py in 0..4usize {
-                let pi = Self::rotate(px, py, self.current_rotation);
-                if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                    let fx = self.current_x + px as i32;
-                    let fy = self.current_y + py as i32;
-                    if fx >= 0 && fx < FIELD_WIDTH as i32 && fy >= 0 && fy < FIELD_HEIGHT as i32 {
-                        self.field[fy as usize][fx as usize] = (self.current_piece + 1) as u8;
-                    }
-                }
-            }
-        }
+        self.field.place(cells, def.color, self.current_x, self.current_y);
+        let size = def.size;
 
-        self.score += 25;
         self.piece_count += 1;
 
-        // Speed up every 10 pieces
-        if self.piece_count % 10 == 0 && self.speed > 10 {
-            self.speed -= 1;
-        }
-
         // Check for completed lines
         self.lines_to_clear.clear();
-        for py in 0..4usize {
+        for py in 0..size {
             let fy = self.current_y + py as i32;
-            if fy >= 0 && fy < (FIELD_HEIGHT as i32 - 1) {
+            if fy >= 0 && self.field.is_row_full(fy as usize) {
                 let row = fy as usize;
-                let mut line_complete = true;
-                for x in 1..(FIELD_WIDTH - 1) {
-                    if self.field[row][x] == 0 {
-                        line_complete = false;
-                        break;
-                    }
-                }
-                if line_complete {
-                    self.lines_to_clear.push(row);
-                    // Mark row as cleared
-                    for x in 1..(FIELD_WIDTH - 1) {
-                        self.field[row][x] = 8;
-                    }
-                }
+                self.lines_to_clear.push(row);
+                // Mark row as cleared
+                self.field.mark_row(row, 8);
             }
         }
 
-        // Sound: LineClear fanfare, or Lock thud (skip Lock if hard-drop already queued)
         let line_count = self.lines_to_clear.len() as u32;
+        self.emit(GameEvent::Locked {
+            placement: Placement {
+                piece: self.current_piece,
+                rotation: self.current_rotation,
+                x: self.current_x,
+                y: self.current_y,
+                spin,
+                lines: line_count,
+                hard_drop,
+            },
+        });
+
+        // Score at the level the lock happened on, before any level-up
+        let result = LockResult { lines: line_count, spin, perfect_clear: self.is_perfect_clear() };
+        let back_to_back = result.is_difficult() && self.scorer.back_to_back;
+        self.score = self.score.saturating_add(self.scorer.lock(result, self.level));
         if line_count > 0 {
-            self.pending_sounds.push(AudioEvent::LineClear(line_count));
-        } else if !matches!(self.pending_sounds.last(), Some(AudioEvent::HardDrop)) {
-            self.pending_sounds.push(AudioEvent::Lock);
+            let kind = ClearKind { spin, perfect_clear: result.perfect_clear, back_to_back };
+            self.emit(GameEvent::LinesCleared { rows: self.lines_to_clear.clone(), kind });
         }
 
-        // Record per-piece analytics
-        let n = self.lines_to_clear.len() as u8;
-        self.lines_cleared += n as u32;
-        match n {
-            1 => self.singles += 1,
-            2 => self.doubles += 1,
-            3 => self.triples += 1,
-            4.. => self.tetrises += 1,
-            _ => {}
+        let level = self.config.start_level.saturating_add(self.stats.lines_cleared / LINES_PER_LEVEL);
+        if level > self.level {
+            self.level = level;
+            self.emit(GameEvent::LevelUp { level });
         }
-        if self.lines_history.len() >= 20 {
-            self.lines_history.pop_front();
-        }
-        self.lines_history.push_back(n);
-
-        // Spawn next piece
-        let mut rng = rand::thread_rng();
-        self.current_piece = self.next_piece;
-        self.current_rotation = 0;
-        self.current_x = (FIELD_WIDTH as i32 / 2) - 2;
-        self.current_y = 0;
-        self.next_piece = rng.gen_range(0..7);
 
-        // Check game over
-        if !self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y) {
-            self.game_over = true;
-            self.pending_sounds.push(AudioEvent::GameOver);
-            self.write_gameover_log();
+        if let Some(reason) = lock_out {
+            self.top_out(reason);
+        } else {
+            // Next piece comes after the line-clear and entry delays
+            self.hold_used = false;
+            self.lock_timer = None;
+            self.phase = if line_count > 0 {
+                Phase::LineClear { elapsed: Duration::ZERO }
+            } else {
+                Phase::Entry { elapsed: Duration::ZERO }
+            };
+            self.advance_phase(Duration::ZERO);
         }
+        self.save_snapshot();
     }
 
-    /// Write the final board state and stats to `tetris_gameover.log` so the
-    /// losing sequence can be inspected after the game ends.
-    fn write_gameover_log(&self) {
-        let path = "tetris_gameover.log";
-        let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) else {
+    /// Record the current state as an undo point (practice mode only).
+    fn save_snapshot(&mut self) {
+        if !self.config.practice {
             return;
-        };
-
-        // Compute board metrics
-        let num_cols = FIELD_WIDTH - 2;
-        let mut heights = vec![0i32; num_cols];
-        for (i, col) in (1..FIELD_WIDTH - 1).enumerate() {
-            for row in 0..(FIELD_HEIGHT - 1) {
-                if self.field[row][col] != 0 {
-                    heights[i] = (FIELD_HEIGHT - 1 - row) as i32;
-                    break;
-                }
-            }
         }
-        let max_height = heights.iter().copied().max().unwrap_or(0);
-        let agg_height: i32 = heights.iter().sum();
-        let mut holes = 0u32;
-        for (i, &h) in heights.iter().enumerate() {
-            if h == 0 { continue; }
-            let col = i + 1;
-            let top_row = (FIELD_HEIGHT as i32 - 1 - h) as usize;
-            for row in (top_row + 1)..(FIELD_HEIGHT - 1) {
-                if self.field[row][col] == 0 { holes += 1; }
-            }
+        let mut history = std::mem::take(&mut self.history);
+        history.states.truncate(history.cursor + 1);
+        let mut snapshot = self.clone();
+        snapshot.events.clear();
+        history.states.push_back(snapshot);
+        if history.states.len() > UNDO_LIMIT + 1 {
+            history.states.pop_front();
         }
-        let bumpiness: i32 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
+        history.cursor = history.states.len() - 1;
+        self.history = history;
+    }
 
-        let _ = writeln!(f, "=== GAME OVER ===");
-        let _ = writeln!(f, "Score: {}  Pieces: {}  Lines: {}", self.score, self.piece_count, self.lines_cleared);
-        let _ = writeln!(f, "1L/2L/3L/4L: {}/{}/{}/{}", self.singles, self.doubles, self.triples, self.tetrises);
-        let _ = writeln!(f, "MaxHt: {}  AggHt: {}  Holes: {}  Bumpy: {}", max_height, agg_height, holes, bumpiness);
-        let _ = writeln!(f, "Heights: {:?}", heights);
-        let _ = writeln!(f, "Board (top to bottom):");
-        for row in 0..(FIELD_HEIGHT - 1) {
-            let mut line = String::new();
-            for col in 1..(FIELD_WIDTH - 1) {
-                let v = self.field[row][col];
-                line.push(if v == 0 { '.' } else { char::from_digit(v as u32, 10).unwrap_or('#') });
-            }
-            let _ = writeln!(f, "  |{}|", line);
+    /// Go back to the state after the previous lock; true if there was one.
+    fn undo(&mut self) -> bool {
+        if !self.config.practice || self.paused || self.history.cursor == 0 {
+            return false;
         }
-        let _ = writeln!(f, "  +{}+", "-".repeat(FIELD_WIDTH - 2));
-        let _ = writeln!(f, "Trend (last 20): {:?}", self.lines_history.iter().copied().collect::<Vec<_>>());
-        let _ = writeln!(f, "");
+        self.restore(self.history.cursor - 1);
+        true
     }
 
-    /// Snapshot of board quality metrics used by the analytics panel.
-    pub fn board_stats(&self) -> BoardStats {
-        let num_cols = FIELD_WIDTH - 2;
-        let mut heights = vec![0i32; num_cols];
-        for (i, col) in (1..FIELD_WIDTH - 1).enumerate() {
-            for row in 0..(FIELD_HEIGHT - 1) {
-                if self.field[row][col] != 0 {
-                    heights[i] = (FIELD_HEIGHT - 1 - row) as i32;
-                    break;
-                }
-            }
+    /// Put back a lock taken away by `undo`; true if there was one.
+    fn redo(&mut self) -> bool {
+        if !self.config.practice || self.paused || self.history.cursor + 1 >= self.history.states.len() {
+            return false;
         }
-        let max_height = heights.iter().copied().max().unwrap_or(0);
-        let mut holes = 0u32;
-        for (i, &h) in heights.iter().enumerate() {
-            if h == 0 {
-                continue;
-            }
-            let col = i + 1;
-            let top_row = (FIELD_HEIGHT as i32 - 1 - h) as usize;
-            for row in (top_row + 1)..(FIELD_HEIGHT - 1) {
-                if self.field[row][col] == 0 {
-                    holes += 1;
-                }
-            }
+        self.restore(self.history.cursor + 1);
+        true
+    }
+
+    /// Return to undo point `index`, keeping the history, pending events and
+    /// whether the AI has played.
+    fn restore(&mut self, index: usize) {
+        let mut history = std::mem::take(&mut self.history);
+        let events = std::mem::take(&mut self.events);
+        let ai_used = self.ai_used;
+        *self = history.states[index].clone();
+        history.cursor = index;
+        self.history = history;
+        self.events = events;
+        self.undo_used = true;
+        self.ai_used |= ai_used;
+    }
+
+    /// Spin check for the active piece as it locks; its last successful
+    /// action must have been a rotation.  The T uses the 3-corner rule.
+    /// With the all-spin rule, any other piece that can't move left, right
+    /// or up counts as well, scored like a mini.
+    fn detect_spin(&self) -> Spin {
+        let LastAction::Rotate { kick } = self.last_action else {
+            return Spin::None;
+        };
+        let def = self.config.pieces.get(self.current_piece);
+        if !self.config.pieces.is_tetromino() || def.name != "T" {
+            let (piece, r, x, y) = (self.current_piece, self.current_rotation, self.current_x, self.current_y);
+            let immobile = !self.does_piece_fit(piece, r, x - 1, y)
+                && !self.does_piece_fit(piece, r, x + 1, y)
+                && !self.does_piece_fit(piece, r, x, y - 1);
+            return if self.config.all_spin && immobile { Spin::Mini } else { Spin::None };
         }
-        let bumpiness: i32 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
-        BoardStats { max_height, holes, bumpiness }
+        spin::t_spin(self.current_rotation, kick, |x, y| {
+            self.field.is_blocked(self.current_x + x, self.current_y + y)
+        })
     }
 
-    /// Store the AI's chosen target placement.
-    pub fn set_ai_target(&mut self, rotation: usize, x: i32) {
-        self.ai_target_rotation = rotation;
-        self.ai_target_x = x;
+    /// True when nothing but rows about to be cleared remains on the board
+    /// (after `clear_lines`, simply: the board is empty).
+    fn is_perfect_clear(&self) -> bool {
+        let width = self.field.width();
+        self.field
+            .rows()
+            .take(self.field.height() - 1)
+            .all(|row| row[1..width - 1].iter().all(|&c| c == 0 || c == 8))
     }
 
-    /// One 50 ms step when AI mode is active.
-    /// Rotates and slides 2 steps toward the target each tick (2× speed),
-    /// then hard-drops once aligned.
-    pub fn ai_step(&mut self) {
-        if self.game_over || self.paused {
-            self.tick();
-            return;
+    /// Pop the front of the preview queue and top it up from the generator.
+    fn take_next_piece(&mut self) -> usize {
+        self.next_queue.push_back(self.generator.next_piece(&mut self.rng));
+        self.next_queue.pop_front().unwrap_or_default()
+    }
+
+    /// End the entry delay: spawn the front of the queue, then apply any
+    /// hold (IHS) and rotation (IRS) buffered during the delay.
+    fn spawn_next(&mut self) {
+        self.phase = Phase::Falling;
+        let piece = self.take_next_piece();
+        self.spawn_piece(piece);
+        if std::mem::take(&mut self.buffered_hold) {
+            self.hold();
         }
-        // Must drain pending line clears via tick() before touching the new
-        // piece.  Without this, a second hard_drop can call lock_piece() which
-        // does lines_to_clear.clear(), orphaning rows already marked as 8 in
-        // the field — they'd never be removed.
-        if !self.lines_to_clear.is_empty() {
-            self.tick();
-            return;
+        if let Some(dir) = self.buffered_rotation.take() {
+            self.rotate_piece(dir);
         }
-        // 1. Rotate toward target rotation
-        if self.current_rotation != self.ai_target_rotation {
-            self.rotate_piece();
+    }
+
+    /// Put `piece` at the spawn position; the game ends if it doesn't fit.
+    fn spawn_piece(&mut self, piece: usize) {
+        self.current_piece = piece;
+        self.current_rotation = 0;
+        self.current_x = spawn_x(self.config.board.width, self.config.pieces.get(piece).size);
+        // Spawn in the two buffer rows just above the visible area, then drop
+        // straight into view if there is room, as the guideline does
+        self.current_y = self.config.board.buffer.saturating_sub(2) as i32;
+        if self.config.board.buffer >= 2 && self.does_piece_fit(piece, 0, self.current_x, self.current_y + 1) {
+            self.current_y += 1;
         }
-        // 2. Slide 2 steps toward target x per tick
-        for _ in 0..2 {
-            if self.current_x < self.ai_target_x {
-                self.move_right();
-            } else if self.current_x > self.ai_target_x {
-                self.move_left();
-            }
+        self.lock_timer = None;
+        self.lock_resets_used = 0;
+        self.lowest_y = self.current_y;
+        self.last_action = LastAction::Move;
+        self.emit(GameEvent::PieceSpawned { piece });
+
+        // Block out: the new piece overlaps the stack
+        if !self.does_piece_fit(self.current_piece, self.current_rotation, self.current_x, self.current_y) {
+            self.top_out(TopOut::Block);
         }
-        // 3. Hard-drop once aligned; otherwise advance gravity normally
-        if self.current_rotation == self.ai_target_rotation
-            && self.current_x == self.ai_target_x
-        {
-            self.hard_drop();
-        } else {
-            self.tick();
+    }
+
+    /// End the game for `reason`.
+    fn top_out(&mut self, reason: TopOut) {
+        self.game_over = true;
+        self.top_out = Some(reason);
+        self.lock_timer = None;
+        self.emit(GameEvent::GameOver { reason });
+    }
+
+    /// Queue `event` for the next `step`.  The game's own analytics are
+    /// kept from the same stream.
+    fn emit(&mut self, event: GameEvent) {
+        self.stats.record(&event);
+        self.events.push(event);
+    }
+
+    /// Swap the active piece with the hold slot (or with the next piece when
+    /// the slot is empty).  Allowed once per piece; locking re-arms it.
+    /// Between pieces the hold is buffered for the next one instead.  True
+    /// if the pieces were swapped or the hold was buffered.
+    fn hold(&mut self) -> bool {
+        if self.game_over || self.paused || self.hold_used {
+            return false;
+        }
+        if !self.piece_active() {
+            self.buffered_hold = true;
+            return true;
         }
+        let held = self.current_piece;
+        let incoming = match self.hold_piece.replace(held) {
+            Some(piece) => piece,
+            None => self.take_next_piece(),
+        };
+        self.hold_used = true;
+        self.gravity_timer = Duration::ZERO;
+        self.emit(GameEvent::Hold { piece: held });
+        self.spawn_piece(incoming);
+        true
     }
 
+    /// Snapshot of board quality metrics used by the analytics panel.
+    pub fn board_stats(&self) -> BoardStats {
+        let bits = self.field.bits();
+        let heights = &bits.column_heights()[..bits.cols()];
+        let max_height = heights.iter().copied().max().unwrap_or(0);
+        let bumpiness: i32 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
+        BoardStats { max_height, holes: bits.holes(), bumpiness }
+    }
+
+    /// Remove the rows wiped during the line-clear delay.
     fn clear_lines(&mut self) {
-        let lines = self.lines_to_clear.clone();
-        let num_lines = lines.len() as u32;
-
-        // Score for line clears: (1 << lines) * 100
-        self.score += (1u32 << num_lines) * 100;
-
-        // Remove cleared lines and shift down
-        for &row in &lines {
-            // Shift everything above this row down by 1
-            for y in (1..=row).rev() {
-                for x in 1..(FIELD_WIDTH - 1) {
-                    self.field[y][x] = self.field[y - 1][x];
-                }
-            }
-            // Clear the top row
-            for x in 1..(FIELD_WIDTH - 1) {
-                self.field[0][x] = 0;
-            }
+        for row in std::mem::take(&mut self.lines_to_clear) {
+            self.field.remove_row(row);
         }
-
-        self.lines_to_clear.clear();
     }
 }
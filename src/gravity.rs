@@ -0,0 +1,120 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Gravity at or above this many cells per frame is "20G": the piece lands
+/// the moment it appears or moves.
+pub const MAX_G: f64 = 20.0;
+/// Slowest gravity a custom table may ask for: a row every ~3 minutes.
+pub const MIN_G: f64 = 0.0001;
+
+/// Frames per second the gravity tables are expressed in.
+const FRAME_RATE: f64 = 60.0;
+
+/// NES (NTSC) frames per row for levels 0-29.
+const NES_FRAMES: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, // 0-9
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2, // 10-19
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 1, // 20-29
+];
+
+/// How fast pieces fall at each level.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GravityCurve {
+    /// Guideline formula: (0.8 - (level - 1) * 0.007)^(level - 1) seconds
+    /// per row, which passes 20G around level 19.
+    #[default]
+    Guideline,
+    /// The NES frame table.  The original stops at 1G on level 29; levels
+    /// past that are 20G.
+    Nes,
+    /// Cells per frame for levels 1, 2, 3, ...; the last entry holds for
+    /// every level beyond the table.
+    Custom(Vec<f64>),
+}
+
+impl GravityCurve {
+    /// Gravity at `level` in cells per frame, capped at `MAX_G`.
+    pub fn cells_per_frame(&self, level: u32) -> f64 {
+        let g = match self {
+            GravityCurve::Guideline => {
+                // 20G from level 20; far beyond it the formula goes negative
+                let l = level.clamp(1, 20) as f64 - 1.0;
+                let seconds = (0.8 - l * 0.007).powf(l);
+                1.0 / (seconds * FRAME_RATE)
+            }
+            GravityCurve::Nes => match NES_FRAMES.get(level as usize) {
+                Some(&frames) => 1.0 / frames as f64,
+                None => MAX_G,
+            },
+            GravityCurve::Custom(table) => {
+                let i = (level.max(1) as usize - 1).min(table.len() - 1);
+                table[i]
+            }
+        };
+        g.min(MAX_G)
+    }
+
+    /// Check a custom table has at least one entry and none below `MIN_G`,
+    /// as `from_str` ensures.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            GravityCurve::Custom(table) if table.is_empty() => Err("gravity table is empty".into()),
+            GravityCurve::Custom(table) if !table.iter().all(|&g| g >= MIN_G) => {
+                Err(format!("gravity values must be at least {}", MIN_G))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Time to fall one row at `level`, or `None` at 20G.
+    pub fn row_time(&self, level: u32) -> Option<Duration> {
+        let g = self.cells_per_frame(level);
+        if g >= MAX_G {
+            return None;
+        }
+        Some(Duration::from_secs_f64(1.0 / (g * FRAME_RATE)))
+    }
+}
+
+impl fmt::Display for GravityCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GravityCurve::Guideline => f.write_str("guideline"),
+            GravityCurve::Nes => f.write_str("nes"),
+            GravityCurve::Custom(table) => {
+                let values: Vec<String> = table.iter().map(|g| g.to_string()).collect();
+                f.write_str(&values.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for GravityCurve {
+    type Err = String;
+
+    /// `guideline`, `nes`, or a comma-separated custom table of cells per
+    /// frame starting at level 1, e.g. `0.02,0.05,0.1,1,20`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guideline" => Ok(GravityCurve::Guideline),
+            "nes" => Ok(GravityCurve::Nes),
+            _ => {
+                let table = s
+                    .split(',')
+                    .map(|v| v.trim().parse::<f64>().ok().filter(|g| *g >= MIN_G))
+                    .collect::<Option<Vec<f64>>>()
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown gravity curve '{}' (expected guideline, nes or a comma-separated list of cells per frame, each at least {})",
+                            s, MIN_G
+                        )
+                    })?;
+                Ok(GravityCurve::Custom(table))
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use tetris::game::{GameConfig, GameState};
+
+/// Entries kept on each table.
+pub const TABLE_SIZE: usize = 10;
+/// Longest player name accepted.
+pub const MAX_NAME: usize = 12;
+
+/// A finished game on a high-score table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+    pub lines: u32,
+    pub pieces: u32,
+    /// Game time played, in milliseconds.
+    pub time_ms: u64,
+    /// Local date and time the game ended, `YYYY-MM-DD HH:MM`.
+    pub date: String,
+    pub seed: u64,
+}
+
+impl Entry {
+    pub fn new(game: &GameState, name: &str) -> Self {
+        Entry {
+            name: name.to_string(),
+            score: game.score,
+            lines: game.stats.lines_cleared,
+            pieces: game.piece_count,
+            time_ms: game.stats.elapsed.as_millis() as u64,
+            date: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            seed: game.seed,
+        }
+    }
+
+    pub fn time(&self) -> Duration {
+        Duration::from_millis(self.time_ms)
+    }
+}
+
+/// The high-score tables, one per mode and rule set, kept in
+/// `highscores.json` under the user's data directory
+/// (`$XDG_DATA_HOME/tetris` on Linux).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    /// Where the tables are saved; `None` when there is nowhere to put them.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Name entered last time, offered again.
+    #[serde(default)]
+    pub last_name: String,
+    /// Best first, at most `TABLE_SIZE` each, keyed by `table_key`.
+    #[serde(default)]
+    tables: BTreeMap<String, Vec<Entry>>,
+}
+
+impl HighScores {
+    /// Read the tables; a missing file gives empty ones.
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = dirs::data_dir().map(|dir| dir.join("tetris").join("highscores.json")) else {
+            return Ok(HighScores::default());
+        };
+        let mut scores = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HighScores::default(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        scores.path = Some(path);
+        Ok(scores)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Keys of the tables that have entries, in order.
+    pub fn keys(&self) -> Vec<&str> {
+        self.tables.keys().map(String::as_str).collect()
+    }
+
+    pub fn table(&self, key: &str) -> &[Entry] {
+        self.tables.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Place `score` would take on table `key`, if it makes the table.
+    /// Ties go below the entries already there.
+    pub fn rank(&self, key: &str, score: u32) -> Option<usize> {
+        let rank = self.table(key).iter().take_while(|e| e.score >= score).count();
+        (rank < TABLE_SIZE && score > 0).then_some(rank)
+    }
+
+    /// Add `entry` to table `key`, returning its place.
+    pub fn insert(&mut self, key: &str, entry: Entry) -> Option<usize> {
+        let rank = self.rank(key, entry.score)?;
+        let table = self.tables.entry(key.to_string()).or_default();
+        table.insert(rank, entry);
+        table.truncate(TABLE_SIZE);
+        Some(rank)
+    }
+}
+
+/// Name of the table a game's score goes on: how it was played and the
+/// rules that affect scoring, e.g.
+/// `marathon: tetromino 10x20, bag, srs, guideline scoring, guideline gravity`.
+pub fn table_key(config: &GameConfig, ai_used: bool) -> String {
+    let mut key = format!(
+        "{}: {} {}, {}, {}, {} scoring, {} gravity",
+        if ai_used { "ai" } else { "marathon" },
+        config.pieces.name,
+        config.board,
+        config.generator,
+        config.rotation,
+        config.scoring,
+        config.gravity,
+    );
+    if config.start_level != 1 {
+        key += &format!(", from level {}", config.start_level);
+    }
+    if config.all_spin {
+        key += ", all-spin";
+    }
+    if config.partial_lock_out {
+        key += ", partial lock out";
+    }
+    key
+}
+
+/// Practice games, and any game where undo was used, stay off the tables.
+pub fn eligible(game: &GameState) -> bool {
+    !game.config.practice && !game.undo_used
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use tetris::game::{Command, GameState};
+
+/// How often a zero ARR or soft-drop interval re-applies while held.
+const INSTANT_RECHECK_MS: u64 = 16;
+
+/// Auto-repeat timing for held movement keys, in milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct InputConfig {
+    /// Delayed auto shift: how long a direction is held before it repeats.
+    pub das_ms: u64,
+    /// Auto repeat rate: interval between repeated shifts (0 = jump to the wall).
+    pub arr_ms: u64,
+    /// Interval between soft-drop steps while Down is held (0 = straight to
+    /// the floor, without locking).
+    pub soft_drop_ms: u64,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig { das_ms: 167, arr_ms: 33, soft_drop_ms: 25 }
+    }
+}
+
+/// Horizontal direction of a held shift key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    Left,
+    Right,
+}
+
+/// Tracks held movement keys and generates DAS/ARR repeats and soft-drop
+/// steps on its own clock, so movement speed does not depend on the
+/// terminal's key-repeat settings.
+///
+/// This needs key release events.  Terminals without the keyboard
+/// enhancement protocol never report releases, so in that case (`enhanced`
+/// false) every press moves exactly once and repeats come from the terminal
+/// as before.
+pub struct InputHandler {
+    config: InputConfig,
+    enhanced: bool,
+    /// Held shift directions; the most recently pressed one wins.
+    held: Vec<Shift>,
+    next_shift: Option<Instant>,
+    next_soft_drop: Option<Instant>,
+}
+
+impl InputHandler {
+    pub fn new(config: InputConfig, enhanced: bool) -> Self {
+        InputHandler { config, enhanced, held: Vec::new(), next_shift: None, next_soft_drop: None }
+    }
+
+    pub fn press_shift(&mut self, dir: Shift, now: Instant, game: &mut GameState) {
+        shift(game, dir);
+        if !self.enhanced {
+            return;
+        }
+        self.held.retain(|&d| d != dir);
+        self.held.push(dir);
+        self.next_shift = Some(now + Duration::from_millis(self.config.das_ms));
+    }
+
+    pub fn release_shift(&mut self, dir: Shift, now: Instant) {
+        self.held.retain(|&d| d != dir);
+        // A direction still held underneath has to charge DAS again
+        self.next_shift = if self.held.is_empty() {
+            None
+        } else {
+            Some(now + Duration::from_millis(self.config.das_ms))
+        };
+    }
+
+    pub fn press_soft_drop(&mut self, now: Instant, game: &mut GameState) {
+        if self.config.soft_drop_ms == 0 {
+            game.apply(Command::SonicDrop);
+        } else {
+            game.apply(Command::SoftDrop);
+        }
+        if self.enhanced {
+            self.next_soft_drop = Some(now + self.soft_drop_interval());
+        }
+    }
+
+    pub fn release_soft_drop(&mut self) {
+        self.next_soft_drop = None;
+    }
+
+    /// Forget all held keys, e.g. when the AI takes over or the game pauses.
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.next_shift = None;
+        self.next_soft_drop = None;
+    }
+
+    /// Apply every repeat that has come due by `now`.
+    pub fn update(&mut self, now: Instant, game: &mut GameState) {
+        if let Some(&dir) = self.held.last() {
+            while let Some(due) = self.next_shift.filter(|&due| due <= now) {
+                if self.config.arr_ms == 0 {
+                    while shift(game, dir) {}
+                } else {
+                    shift(game, dir);
+                }
+                self.next_shift = Some(due + self.arr_interval());
+            }
+        }
+        while let Some(due) = self.next_soft_drop.filter(|&due| due <= now) {
+            if self.config.soft_drop_ms == 0 {
+                game.apply(Command::SonicDrop);
+            } else {
+                game.apply(Command::SoftDrop);
+            }
+            self.next_soft_drop = Some(due + self.soft_drop_interval());
+        }
+    }
+
+    /// Earliest time `update` has work to do, for sizing the event poll.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let shift = self.next_shift.filter(|_| !self.held.is_empty());
+        match (shift, self.next_soft_drop) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    // Zero intervals move as far as possible each time, so they only need
+    // re-checking now and then (the piece may have changed underneath).
+    fn arr_interval(&self) -> Duration {
+        Duration::from_millis(if self.config.arr_ms == 0 { INSTANT_RECHECK_MS } else { self.config.arr_ms })
+    }
+
+    fn soft_drop_interval(&self) -> Duration {
+        Duration::from_millis(if self.config.soft_drop_ms == 0 { INSTANT_RECHECK_MS } else { self.config.soft_drop_ms })
+    }
+}
+
+/// Shift the active piece one column; returns whether it moved.
+fn shift(game: &mut GameState, dir: Shift) -> bool {
+    let x = game.current_x;
+    match dir {
+        Shift::Left => game.apply(Command::MoveLeft),
+        Shift::Right => game.apply(Command::MoveRight),
+    }
+    game.current_x != x
+}
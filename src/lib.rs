@@ -0,0 +1,22 @@
+//! The game engine: rules, pieces, scoring, analytics, replays and the AI,
+//! with no terminal or audio.  A front end creates a `GameState`, feeds it
+//! `Command`s and calls `step` at a fixed interval, reacting to the
+//! `GameEvent`s it returns, usually by handing them to `Subscriber`s; the
+//! `tetris` binary is the terminal front end.
+
+pub mod ai;
+pub mod bitboard;
+pub mod events;
+pub mod field;
+pub mod game;
+pub mod gravity;
+pub mod pieces;
+pub mod replay;
+pub mod rotation;
+pub mod save;
+pub mod scoring;
+pub mod spin;
+pub mod stats;
+
+pub use events::{GameEvent, Subscriber};
+pub use game::{Command, GameConfig, GameState};
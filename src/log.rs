@@ -0,0 +1,181 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use tetris::ai::Weights;
+use tetris::events::{GameEvent, Subscriber};
+use tetris::game::{BoardStats, GameState};
+
+/// Writes the game-over log when the game ends, and a JSON line to
+/// `json_path` when one is set.
+pub struct GameOverLog {
+    pub json_path: Option<PathBuf>,
+    /// Weights the AI plays with, logged for games it played part of.
+    pub ai_weights: Weights,
+}
+
+impl Subscriber for GameOverLog {
+    fn on_event(&mut self, game: &GameState, event: &GameEvent) {
+        if let GameEvent::GameOver { .. } = event {
+            write_gameover_log(game);
+            if let Some(path) = &self.json_path {
+                write_gameover_json(path, game, self.ai_weights);
+            }
+        }
+    }
+}
+
+/// One line of the JSON game-over log: the metrics of the text log, plus
+/// what is needed to tell games apart and replay them.
+#[derive(Serialize)]
+struct GameOverRecord<'a> {
+    /// Local date and time the game ended, RFC 3339.
+    date: String,
+    /// `marathon`, `ai` (the AI played at least part of it) or `practice`.
+    mode: &'static str,
+    seed: u64,
+    reason: Option<String>,
+    score: u32,
+    level: u32,
+    pieces: u32,
+    lines: u32,
+    /// Game time played, excluding pauses.
+    duration_ms: u64,
+    generator: String,
+    piece_set: &'a str,
+    rotation: String,
+    scoring: String,
+    gravity: String,
+    board: String,
+    singles: u32,
+    doubles: u32,
+    triples: u32,
+    tetrises: u32,
+    spin_clears: u32,
+    perfect_clears: u32,
+    max_height: i32,
+    aggregate_height: i32,
+    holes: u32,
+    bumpiness: i32,
+    /// Column heights, left to right.
+    heights: &'a [i32],
+    /// Lines cleared by each of the last pieces, oldest first.
+    trend: Vec<u8>,
+    /// Rows above the visible board at the top of `cells`.
+    buffer_rows: usize,
+    /// Cell colors top to bottom, left to right; 0 is empty.
+    cells: Vec<&'a [u8]>,
+    ai_weights: Option<Weights>,
+}
+
+/// Append the final board state and stats to `path` as one JSON object per
+/// line, for scripts going through many games.
+pub fn write_gameover_json(path: &Path, game: &GameState, ai_weights: Weights) {
+    let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) else {
+        return;
+    };
+
+    let width = game.field.width();
+    let heights = &game.field.bits().column_heights()[..width - 2];
+    let BoardStats { max_height, holes, bumpiness } = game.board_stats();
+    let stats = &game.stats;
+    let config = &game.config;
+    let record = GameOverRecord {
+        date: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        mode: if config.practice {
+            "practice"
+        } else if game.ai_used {
+            "ai"
+        } else {
+            "marathon"
+        },
+        seed: game.seed,
+        reason: game.top_out.map(|reason| reason.to_string()),
+        score: game.score,
+        level: game.level,
+        pieces: game.piece_count,
+        lines: stats.lines_cleared,
+        duration_ms: stats.elapsed.as_millis() as u64,
+        generator: config.generator.to_string(),
+        piece_set: &config.pieces.name,
+        rotation: config.rotation.to_string(),
+        scoring: config.scoring.to_string(),
+        gravity: config.gravity.to_string(),
+        board: config.board.to_string(),
+        singles: stats.singles,
+        doubles: stats.doubles,
+        triples: stats.triples,
+        tetrises: stats.tetrises,
+        spin_clears: stats.spin_clears,
+        perfect_clears: stats.perfect_clears,
+        max_height,
+        aggregate_height: heights.iter().sum(),
+        holes,
+        bumpiness,
+        heights,
+        trend: stats.lines_history.iter().copied().collect(),
+        buffer_rows: config.board.buffer,
+        // Drop the wall columns and the floor row
+        cells: (0..game.field.height() - 1).map(|row| &game.field[row][1..width - 1]).collect(),
+        ai_weights: game.ai_used.then_some(ai_weights),
+    };
+    if let Ok(line) = serde_json::to_string(&record) {
+        let _ = writeln!(f, "{}", line);
+    }
+}
+
+/// Write the final board state and stats to `tetris_gameover.log` so the
+/// losing sequence can be inspected after the game ends.
+pub fn write_gameover_log(game: &GameState) {
+    let path = "tetris_gameover.log";
+    let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) else {
+        return;
+    };
+
+    // Compute board metrics
+    let (width, height) = (game.field.width(), game.field.height());
+    let num_cols = width - 2;
+    let heights = &game.field.bits().column_heights()[..num_cols];
+    let BoardStats { max_height, holes, bumpiness } = game.board_stats();
+    let agg_height: i32 = heights.iter().sum();
+    let stats = &game.stats;
+
+    let _ = writeln!(f, "=== GAME OVER ===");
+    if let Some(reason) = game.top_out {
+        let _ = writeln!(f, "Reason: {} ({})", reason, reason.description());
+    }
+    let _ = writeln!(
+        f,
+        "Score: {} ({})  Level: {}  Pieces: {}  Lines: {}",
+        game.score, game.config.scoring, game.level, game.piece_count, stats.lines_cleared
+    );
+    let _ = writeln!(
+        f,
+        "Seed: {}  Generator: {}  Pieces: {}",
+        game.seed, game.config.generator, game.config.pieces.name
+    );
+    let _ = writeln!(
+        f,
+        "1L/2L/3L/4L/Spin: {}/{}/{}/{}/{}  Perfect clears: {}",
+        stats.singles, stats.doubles, stats.triples, stats.tetrises, stats.spin_clears, stats.perfect_clears
+    );
+    let _ = writeln!(f, "MaxHt: {}  AggHt: {}  Holes: {}  Bumpy: {}", max_height, agg_height, holes, bumpiness);
+    let _ = writeln!(f, "Heights: {:?}", heights);
+    let _ = writeln!(f, "Board {} (top to bottom, buffer rows above the line):", game.config.board);
+    for row in 0..(height - 1) {
+        if row == game.config.board.buffer {
+            let _ = writeln!(f, "  +{}+", "-".repeat(num_cols));
+        }
+        let mut line = String::new();
+        for col in 1..(width - 1) {
+            let v = game.field[row][col];
+            line.push(if v == 0 { '.' } else { char::from_digit(v as u32, 10).unwrap_or('#') });
+        }
+        let _ = writeln!(f, "  |{}|", line);
+    }
+    let _ = writeln!(f, "  +{}+", "-".repeat(num_cols));
+    let _ = writeln!(f, "Trend (last 20): {:?}", stats.lines_history.iter().copied().collect::<Vec<_>>());
+    let _ = writeln!(f);
+}
@@ -1,71 +1,533 @@
-mod ai;
 mod audio;
-mod game;
+mod highscores;
+mod input;
+mod log;
 mod ui;
 
-use std::{io, time::Duration};
+use std::{
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use game::GameState;
-use ui::render_ui;
+use highscores::{eligible, table_key, Entry, HighScores, MAX_NAME};
+use input::{InputConfig, InputHandler, Shift};
+use tetris::ai::Bot;
+use tetris::events::{dispatch, GameEvent, Subscriber};
+use tetris::field::BoardSize;
+use tetris::game::{Command, GameConfig, GameState};
+use tetris::pieces::PieceSet;
+use tetris::replay::{Player, Recorder, Replay};
+use tetris::save;
+use tetris::rotation::RotateDir;
+use ui::{render_high_scores, render_menu, render_ui, Effects, Hud, NameEntry, ReplayStatus};
+
+/// Fixed simulation step: the game clock always advances in slices of this
+/// size, however irregularly the loop wakes up.
+const STEP: Duration = Duration::from_millis(10);
+/// Interval between AI moves while AI mode is on.
+const AI_STEP: Duration = Duration::from_millis(50);
+/// Most simulation time caught up in one go after a stall (e.g. a suspended
+/// terminal), so the game doesn't fast-forward.
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+/// Where each game's replay is written unless `--record` says otherwise.
+const DEFAULT_RECORD: &str = "tetris_replay.json";
+/// Where an unfinished game is saved unless `--save` says otherwise.
+const DEFAULT_SAVE: &str = "tetris_save.json";
+/// How far the arrow keys seek during a replay.
+const SEEK: Duration = Duration::from_secs(5);
+/// Replay speeds the up/down keys step through.
+const REPLAY_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+/// Entries of the start menu.
+const MENU: [&str; 3] = ["Play", "High scores", "Quit"];
+
+const USAGE: &str = "\
+Usage: tetris [OPTIONS]
+
+Options:
+  --generator <NAME>   Piece randomizer: bag (default), nes, tgm, random
+  --seed <N>           RNG seed, to replay a game exactly (default: random)
+  --rotation <NAME>    Rotation system: srs (default), classic
+  --pieces <SET>       Piece set: tetromino (default), pentomino, or a file
+  --preview <N>        Number of next pieces shown, 1-6 (default: 3)
+  --lock-delay <MS>    Time a grounded piece waits before locking (default: 500)
+  --lock-resets <N>    Moves/rotations that restart the lock delay (default: 15)
+  --are <MS>           Entry delay before each new piece (default: 100)
+  --line-clear-delay <MS>
+                       Length of the line-clear animation (default: 300)
+  --level <N>          Starting level, 0-99 (default: 1)
+  --gravity <CURVE>    Gravity curve: guideline (default), nes, or a
+                       comma-separated list of cells/frame per level
+  --scoring <RULES>    Scoring rules: guideline (default), classic
+  --all-spin           Count immobile rotations of any piece as spins
+  --board <WxH>        Visible board size, 4-16 wide and 4-40 tall (default: 10x20)
+  --buffer <ROWS>      Hidden rows above the board where pieces spawn, 0-40 (default: 20)
+  --partial-lock-out   End the game when a piece locks even partly above the board
+  --practice           Practice mode: locks can be undone (u) and redone (r);
+                       scores don't count for the high-score table
+  --das <MS>           Delay before a held direction auto-repeats (default: 167)
+  --arr <MS>           Interval between auto-repeated moves, 0 = instant (default: 33)
+  --soft-drop <MS>     Interval between soft-drop steps, 0 = instant (default: 25)
+  --fps <N>            Target frame rate for drawing, 1-240 (default: 60)
+  --record <FILE>      Where to write the game's replay (default: tetris_replay.json)
+  --json-log <FILE>    Also log each game over to FILE as a line of JSON
+  --replay <FILE>      Watch a recorded game instead of playing
+  --save <FILE>        Where an unfinished game is saved (default: tetris_save.json)
+  --resume             Continue the game saved there, skipping the menu
+                       (other game options are ignored)
+  -h, --help           Print this help";
+
+/// Everything set from the command line.
+struct Options {
+    config: GameConfig,
+    input: InputConfig,
+    fps: u32,
+    record: PathBuf,
+    json_log: Option<PathBuf>,
+    replay: Option<Replay>,
+    save: PathBuf,
+    resume: bool,
+}
+
+/// Parse command-line options.
+fn parse_args() -> Result<Options, String> {
+    let mut config = GameConfig::default();
+    let mut input = InputConfig::default();
+    let mut fps = 60;
+    let mut record = PathBuf::from(DEFAULT_RECORD);
+    let mut json_log = None;
+    let mut replay = None;
+    let mut save = PathBuf::from(DEFAULT_SAVE);
+    let mut resume = false;
+    let mut buffer = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--generator" => {
+                let value = args.next().ok_or("--generator needs a value")?;
+                config.generator = value.parse()?;
+            }
+            "--seed" => {
+                let value = args.next().ok_or("--seed needs a value")?;
+                let seed = value.parse().map_err(|_| format!("invalid seed '{}'", value))?;
+                config.seed = Some(seed);
+            }
+            "--rotation" => {
+                let value = args.next().ok_or("--rotation needs a value")?;
+                config.rotation = value.parse()?;
+            }
+            "--pieces" => {
+                let value = args.next().ok_or("--pieces needs a value")?;
+                config.pieces = Arc::new(PieceSet::from_arg(&value)?);
+            }
+            "--preview" => {
+                let value = args.next().ok_or("--preview needs a value")?;
+                config.preview = value
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=GameConfig::MAX_PREVIEW).contains(n))
+                    .ok_or_else(|| format!("--preview must be 1-{}", GameConfig::MAX_PREVIEW))?;
+            }
+            "--lock-delay" => {
+                let value = args.next().ok_or("--lock-delay needs a value")?;
+                config.lock_delay_ms = value.parse().map_err(|_| format!("invalid lock delay '{}'", value))?;
+            }
+            "--lock-resets" => {
+                let value = args.next().ok_or("--lock-resets needs a value")?;
+                config.lock_resets = value.parse().map_err(|_| format!("invalid lock reset count '{}'", value))?;
+            }
+            "--are" => {
+                let value = args.next().ok_or("--are needs a value")?;
+                config.are_ms = value.parse().map_err(|_| format!("invalid ARE '{}'", value))?;
+            }
+            "--line-clear-delay" => {
+                let value = args.next().ok_or("--line-clear-delay needs a value")?;
+                config.line_clear_ms = value.parse().map_err(|_| format!("invalid line-clear delay '{}'", value))?;
+            }
+            "--level" => {
+                let value = args.next().ok_or("--level needs a value")?;
+                config.start_level = value
+                    .parse()
+                    .ok()
+                    .filter(|n| *n <= GameConfig::MAX_LEVEL)
+                    .ok_or_else(|| format!("--level must be 0-{}", GameConfig::MAX_LEVEL))?;
+            }
+            "--gravity" => {
+                let value = args.next().ok_or("--gravity needs a value")?;
+                config.gravity = value.parse()?;
+            }
+            "--scoring" => {
+                let value = args.next().ok_or("--scoring needs a value")?;
+                config.scoring = value.parse()?;
+            }
+            "--all-spin" => config.all_spin = true,
+            "--board" => {
+                let value = args.next().ok_or("--board needs a value")?;
+                config.board = value.parse()?;
+            }
+            "--buffer" => {
+                let value = args.next().ok_or("--buffer needs a value")?;
+                let rows = value
+                    .parse()
+                    .ok()
+                    .filter(|n| *n <= BoardSize::MAX_BUFFER)
+                    .ok_or_else(|| format!("--buffer must be 0-{}", BoardSize::MAX_BUFFER))?;
+                buffer = Some(rows);
+            }
+            "--partial-lock-out" => config.partial_lock_out = true,
+            "--practice" => config.practice = true,
+            "--das" => {
+                let value = args.next().ok_or("--das needs a value")?;
+                input.das_ms = value.parse().map_err(|_| format!("invalid DAS '{}'", value))?;
+            }
+            "--arr" => {
+                let value = args.next().ok_or("--arr needs a value")?;
+                input.arr_ms = value.parse().map_err(|_| format!("invalid ARR '{}'", value))?;
+            }
+            "--soft-drop" => {
+                let value = args.next().ok_or("--soft-drop needs a value")?;
+                input.soft_drop_ms = value.parse().map_err(|_| format!("invalid soft-drop interval '{}'", value))?;
+            }
+            "--fps" => {
+                let value = args.next().ok_or("--fps needs a value")?;
+                fps = value
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=240).contains(n))
+                    .ok_or("--fps must be 1-240")?;
+            }
+            "--record" => {
+                record = args.next().ok_or("--record needs a value")?.into();
+            }
+            "--json-log" => {
+                json_log = Some(args.next().ok_or("--json-log needs a value")?.into());
+            }
+            "--replay" => {
+                let value = args.next().ok_or("--replay needs a value")?;
+                replay = Some(Replay::load(value.as_ref())?);
+            }
+            "--save" => {
+                save = args.next().ok_or("--save needs a value")?.into();
+            }
+            "--resume" => resume = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(format!("unrecognised argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    if let Some(rows) = buffer {
+        config.board.buffer = rows;
+    }
+    Ok(Options { config, input, fps, record, json_log, replay, save, resume })
+}
 
 fn main() -> io::Result<()> {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            std::process::exit(2);
+        }
+    };
+    let resumed = if options.resume {
+        match save::load(&options.save) {
+            Ok(saved) => Some(saved),
+            Err(msg) => {
+                eprintln!("error: cannot resume: {}", msg);
+                std::process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
     // --- Terminal setup ---
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    // Key release events drive DAS/ARR; without them we fall back to the
+    // terminal's own key repeat.
+    let enhanced = matches!(supports_keyboard_enhancement(), Ok(true));
+    if enhanced {
+        execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
-    let result = run(&mut terminal);
+    let mut messages = Vec::new();
+    let result = match options.replay {
+        Some(replay) => run_replay(&mut terminal, replay, options.fps),
+        None => {
+            let input = InputHandler::new(options.input, enhanced);
+            session(&mut terminal, &options, resumed, input, &mut messages)
+        }
+    };
 
     // --- Terminal cleanup ---
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
+    for message in messages {
+        match message {
+            Ok(msg) => println!("{}", msg),
+            Err(msg) => eprintln!("warning: {}", msg),
+        }
+    }
     result
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    let mut game = GameState::new();
-    let mut last_piece_count = game.piece_count;
-    let mut audio = audio::AudioManager::new(); // None if no audio device
-
+/// Menu and games until the player quits.  A `resumed` game is played
+/// straight away; after each game its replay is written, and an unfinished
+/// one is saved for `--resume`.  Anything to tell the player once the
+/// terminal is restored goes in `messages` (`Err` for warnings).
+fn session(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    options: &Options,
+    mut resumed: Option<(GameState, Replay)>,
+    mut input: InputHandler,
+    messages: &mut Vec<Result<String, String>>,
+) -> io::Result<()> {
+    let mut scores = HighScores::load().unwrap_or_else(|msg| {
+        messages.push(Err(format!("high scores not loaded and won't be saved: {}", msg)));
+        HighScores::default()
+    });
     loop {
-        // Draw frame
-        terminal.draw(|f| render_ui(f, &game))?;
+        let from_save = resumed.is_some();
+        let (game, recorder) = match resumed.take() {
+            Some((mut game, replay)) => {
+                // Come back paused, so the player has a moment to get ready
+                if !game.paused {
+                    game.apply(Command::TogglePause);
+                }
+                (game, Recorder::resume(replay))
+            }
+            None => {
+                if !run_menu(terminal, &scores, &options.config)? {
+                    return Ok(());
+                }
+                let game = GameState::new(options.config.clone());
+                let recorder = Recorder::new(&game, STEP);
+                (game, recorder)
+            }
+        };
+        input.reset();
+        let gameover_log = log::GameOverLog { json_path: options.json_log.clone(), ai_weights: Default::default() };
+        let (game, replay, next, saved) = run(terminal, game, recorder, &mut input, gameover_log, options, &mut scores)?;
 
-        // Drain and play any sounds queued by the game logic
-        if let Some(ref mut mgr) = audio {
-            for event in game.pending_sounds.drain(..) {
-                mgr.play_event(&event);
+        if let Err(msg) = replay.save(&options.record) {
+            messages.push(Err(format!("could not save replay: {}", msg)));
+        }
+        // Keep an unfinished game for --resume
+        if !game.game_over {
+            match save::save(&options.save, &game, &replay) {
+                Ok(()) => messages.push(Ok(format!(
+                    "Game saved to {}; continue it with --resume",
+                    options.save.display()
+                ))),
+                Err(msg) => messages.push(Err(format!("could not save the game: {}", msg))),
             }
-        } else {
-            game.pending_sounds.clear();
+        } else if from_save || saved {
+            // The saved game has now been played out; don't offer it again
+            let _ = std::fs::remove_file(&options.save);
+        }
+        if next == After::Quit {
+            return Ok(());
+        }
+    }
+}
+
+/// Show the start menu until the player picks something: `true` to play a
+/// game, `false` to quit.  High scores open on the table for `config`.
+fn run_menu(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    scores: &HighScores,
+    config: &GameConfig,
+) -> io::Result<bool> {
+    let mut selected = 0;
+    // Page of the high-score tables being shown, if any
+    let mut page: Option<usize> = None;
+    let current = table_key(config, false);
+    let mut keys: Vec<String> = scores.keys().into_iter().map(str::to_string).collect();
+    if !keys.contains(&current) {
+        keys.push(current.clone());
+        keys.sort();
+    }
+
+    loop {
+        terminal.draw(|f| match page {
+            Some(page) => render_high_scores(f, &keys[page], scores.table(&keys[page]), page, keys.len()),
+            None => render_menu(f, &MENU, selected),
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match (page, key.code) {
+            (Some(_), KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) => page = None,
+            (Some(p), KeyCode::Left) => page = Some((p + keys.len() - 1) % keys.len()),
+            (Some(p), KeyCode::Right) => page = Some((p + 1) % keys.len()),
+            (None, KeyCode::Up) => selected = (selected + MENU.len() - 1) % MENU.len(),
+            (None, KeyCode::Down) => selected = (selected + 1) % MENU.len(),
+            (None, KeyCode::Enter) => match selected {
+                0 => return Ok(true),
+                1 => page = keys.iter().position(|k| *k == current),
+                _ => return Ok(false),
+            },
+            (None, KeyCode::Char('q') | KeyCode::Esc) => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// Where to go once a game is left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum After {
+    Menu,
+    Quit,
+}
+
+/// Play `game` until the player leaves it, returning it with its replay,
+/// where to go next and whether it was saved.  `s` saves the game to the
+/// `--save` path without stopping; a score that makes `scores` asks for a
+/// name at game over.
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mut game: GameState,
+    mut recorder: Recorder,
+    input: &mut InputHandler,
+    mut gameover_log: log::GameOverLog,
+    options: &Options,
+    scores: &mut HighScores,
+) -> io::Result<(GameState, Replay, After, bool)> {
+    let mut bot = Bot::default();
+    gameover_log.ai_weights = bot.weights;
+    let mut ai_mode = false;
+    let mut name_entry: Option<NameEntry> = None;
+    let mut scored = false;
+    // The game wrote the save file with `s`
+    let mut saved = false;
+    let mut audio = audio::AudioManager::new(); // None if no audio device
+    let mut effects = Effects::default();
+
+    let frame = Duration::from_secs(1) / options.fps;
+    let mut last_update = Instant::now();
+    let mut lag = Duration::ZERO; // simulation time owed to the game
+    let mut ai_timer = Duration::ZERO;
+    let mut next_frame = last_update;
+
+    let after = loop {
+        // Draw at most `fps` times a second
+        if Instant::now() >= next_frame {
+            let hud = Hud::Play { ai_mode, name_entry: name_entry.as_ref() };
+            terminal.draw(|f| render_ui(f, &game, &effects, &hud))?;
+            next_frame = (next_frame + frame).max(Instant::now());
         }
 
-        // Poll for input with 50ms timeout
-        if event::poll(Duration::from_millis(50))? {
+        // Wait for input until the next simulation step, frame or
+        // auto-repeat is due
+        let next_step = last_update + STEP.saturating_sub(lag);
+        let deadline = [Some(next_step), Some(next_frame), input.next_deadline()]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(next_step);
+        if event::poll(deadline.saturating_duration_since(Instant::now()))? {
             if let Event::Key(key) = event::read()? {
-                // Only process key-press events (ignore release/repeat on some platforms)
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
+                let now = Instant::now();
+                let controls = !game.game_over && !ai_mode;
+                // Typing a name for the high scores takes every key
+                if let Some(entry) = name_entry.as_mut().filter(|e| !e.saved) {
+                    if key.kind == KeyEventKind::Press {
+                        match key.code {
+                            KeyCode::Char(c) if !c.is_control() && entry.name.chars().count() < MAX_NAME => {
+                                entry.name.push(c);
+                            }
+                            KeyCode::Backspace => {
+                                entry.name.pop();
+                            }
+                            KeyCode::Enter if !entry.name.trim().is_empty() => {
+                                let name = entry.name.trim().to_string();
+                                let key = table_key(&game.config, game.ai_used);
+                                if let Some(rank) = scores.insert(&key, Entry::new(&game, &name)) {
+                                    entry.rank = rank;
+                                }
+                                scores.last_name = name;
+                                entry.saved = true;
+                                if scores.save().is_err() {
+                                    effects.notify("SAVE FAILED");
+                                }
+                            }
+                            KeyCode::Esc => name_entry = None,
+                            _ => {}
+                        }
+                    }
+                    continue;
+                }
+                match (key.kind, key.code) {
+                    // Held movement keys go through the auto-repeat layer
+                    (KeyEventKind::Press, KeyCode::Left) if controls => {
+                        input.press_shift(Shift::Left, now, &mut game);
+                    }
+                    (KeyEventKind::Press, KeyCode::Right) if controls => {
+                        input.press_shift(Shift::Right, now, &mut game);
+                    }
+                    (KeyEventKind::Press, KeyCode::Down) if controls => {
+                        input.press_soft_drop(now, &mut game);
+                    }
+                    (KeyEventKind::Release, KeyCode::Left) => input.release_shift(Shift::Left, now),
+                    (KeyEventKind::Release, KeyCode::Right) => input.release_shift(Shift::Right, now),
+                    (KeyEventKind::Release, KeyCode::Down) => input.release_soft_drop(),
+                    // Everything else reacts to presses only (ignore release/repeat)
+                    (KeyEventKind::Press, code) => match code {
                         KeyCode::Char('q') | KeyCode::Esc => {
-                            break;
+                            break After::Quit;
                         }
                         KeyCode::Enter if game.game_over => {
-                            break;
+                            break After::Menu;
+                        }
+                        KeyCode::Char('u') if !ai_mode => {
+                            game.apply(Command::Undo);
+                            input.reset();
+                        }
+                        KeyCode::Char('r') if !ai_mode => {
+                            game.apply(Command::Redo);
+                            input.reset();
+                        }
+                        KeyCode::Char('s') if !game.game_over => {
+                            match save::save(&options.save, &game, &recorder.snapshot(&game)) {
+                                Ok(()) => {
+                                    saved = true;
+                                    effects.notify("GAME SAVED");
+                                }
+                                Err(_) => effects.notify("SAVE FAILED"),
+                            }
                         }
                         KeyCode::Char('p') if !game.game_over => {
-                            game.paused = !game.paused;
+                            game.apply(Command::TogglePause);
+                            input.reset();
                         }
                         KeyCode::Char('m') => {
                             if let Some(ref mut mgr) = audio {
@@ -73,47 +535,165 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()>
                             }
                         }
                         KeyCode::Char('a') if !game.game_over => {
-                            game.ai_mode = !game.ai_mode;
-                            if game.ai_mode {
-                                let (rot, x) = ai::compute_best_move(&game);
-                                game.set_ai_target(rot, x);
-                                last_piece_count = game.piece_count;
-                            }
-                        }
-                        // Movement keys — only when AI is off
-                        KeyCode::Left if !game.game_over && !game.ai_mode => {
-                            game.move_left();
+                            ai_mode = !ai_mode;
+                            // Its score goes on the AI table from now on, even if resumed
+                            game.ai_used |= ai_mode;
+                            input.reset();
+                            bot.reset();
                         }
-                        KeyCode::Right if !game.game_over && !game.ai_mode => {
-                            game.move_right();
+                        // Other movement keys — only when AI is off
+                        KeyCode::Up | KeyCode::Char('x') if controls => {
+                            game.apply(Command::Rotate(RotateDir::Cw));
                         }
-                        KeyCode::Down if !game.game_over && !game.ai_mode => {
-                            game.move_down();
+                        KeyCode::Char('z') if controls => {
+                            game.apply(Command::Rotate(RotateDir::Ccw));
                         }
-                        KeyCode::Up if !game.game_over && !game.ai_mode => {
-                            game.rotate_piece();
+                        KeyCode::Char('c') if controls => {
+                            game.apply(Command::Hold);
                         }
-                        KeyCode::Char(' ') if !game.game_over && !game.ai_mode => {
-                            game.hard_drop();
+                        KeyCode::Char(' ') if controls => {
+                            game.apply(Command::HardDrop);
                         }
                         _ => {}
-                    }
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if !game.game_over && !ai_mode {
+            input.update(now, &mut game);
+        }
+
+        // Advance the game clock in fixed steps for the real time elapsed
+        lag = (lag + (now - last_update)).min(MAX_CATCH_UP);
+        last_update = now;
+        while lag >= STEP {
+            lag -= STEP;
+            if ai_mode {
+                ai_timer += STEP;
+                if ai_timer >= AI_STEP {
+                    ai_timer -= AI_STEP;
+                    bot.step(&mut game);
                 }
             }
-        } else {
-            // Timeout → game tick
-            if game.ai_mode {
-                // Recompute target when a new piece has spawned and the board
-                // is settled (no pending line-clear animation).
-                if game.piece_count != last_piece_count && game.lines_to_clear.is_empty() {
-                    last_piece_count = game.piece_count;
-                    let (rot, x) = ai::compute_best_move(&game);
-                    game.set_ai_target(rot, x);
+            if !game.paused && !game.game_over {
+                effects.update(STEP);
+            }
+            let events = game.step(STEP);
+            let mut subscribers: Vec<&mut dyn Subscriber> = vec![&mut effects, &mut gameover_log, &mut recorder];
+            if let Some(ref mut mgr) = audio {
+                subscribers.push(mgr);
+            }
+            dispatch(&game, &events, &mut subscribers);
+        }
+
+        // Offer a place on the high scores once the game is over
+        if game.game_over && !scored {
+            scored = true;
+            if eligible(&game) {
+                if let Some(rank) = scores.rank(&table_key(&game.config, game.ai_used), game.score) {
+                    name_entry = Some(NameEntry { rank, name: scores.last_name.clone(), saved: false });
+                }
+            }
+        }
+    };
+
+    let replay = recorder.finish(&game);
+    Ok((game, replay, after, saved))
+}
+
+/// Watch a recorded game, with play/pause, seeking, speed control and
+/// stepping a piece at a time.
+fn run_replay(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, replay: Replay, fps: u32) -> io::Result<()> {
+    let step = replay.step();
+    let mut player = Player::new(replay);
+    let mut audio = audio::AudioManager::new();
+    let mut effects = Effects::default();
+    let mut speed = REPLAY_SPEEDS.iter().position(|&s| s == 1.0).unwrap_or(0);
+    let mut paused = false;
+
+    let frame = Duration::from_secs(1) / fps;
+    let mut last_update = Instant::now();
+    let mut lag = Duration::ZERO;
+    let mut next_frame = last_update;
+
+    loop {
+        if Instant::now() >= next_frame {
+            let status = ReplayStatus {
+                time: player.time(),
+                duration: player.replay().duration(),
+                speed: REPLAY_SPEEDS[speed],
+                paused,
+            };
+            terminal.draw(|f| render_ui(f, player.game(), &effects, &Hud::Replay(status)))?;
+            next_frame = (next_frame + frame).max(Instant::now());
+        }
+
+        let wait = next_frame.saturating_duration_since(Instant::now()).min(step);
+        if event::poll(wait)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
                 }
-                game.ai_step();
-            } else {
-                game.tick();
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Enter if player.game().game_over => break,
+                    KeyCode::Char(' ') | KeyCode::Char('p') => paused = !paused,
+                    KeyCode::Char('m') => {
+                        if let Some(ref mut mgr) = audio {
+                            mgr.toggle_music();
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Char('+') => speed = (speed + 1).min(REPLAY_SPEEDS.len() - 1),
+                    KeyCode::Down | KeyCode::Char('-') => speed = speed.saturating_sub(1),
+                    KeyCode::Left => {
+                        player.seek(player.time().saturating_sub(SEEK));
+                        effects = Effects::default();
+                    }
+                    KeyCode::Right => {
+                        player.seek(player.time() + SEEK);
+                        effects = Effects::default();
+                    }
+                    KeyCode::Home => {
+                        player.restart();
+                        effects = Effects::default();
+                    }
+                    KeyCode::Char('.') => {
+                        // Run on to the next piece, then hold there
+                        paused = true;
+                        while !player.is_finished() {
+                            let events = player.advance();
+                            if events.iter().any(|e| matches!(e, GameEvent::PieceSpawned { .. })) {
+                                break;
+                            }
+                        }
+                        effects = Effects::default();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Advance the game clock at the chosen speed
+        let now = Instant::now();
+        let speed_factor = REPLAY_SPEEDS[speed];
+        if !paused {
+            lag = (lag + (now - last_update).mul_f64(speed_factor)).min(MAX_CATCH_UP.mul_f64(speed_factor));
+        }
+        last_update = now;
+        while lag >= step {
+            lag -= step;
+            if !player.game().game_over {
+                effects.update(step);
+            }
+            let events = player.advance();
+            let mut subscribers: Vec<&mut dyn Subscriber> = vec![&mut effects];
+            if let Some(ref mut mgr) = audio {
+                subscribers.push(mgr);
             }
+            dispatch(player.game(), &events, &mut subscribers);
         }
     }
 
@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::PieceMask;
+use crate::field::BoardSize;
+use crate::rotation::KickTable;
+
+/// One piece of a set, with its cells precomputed for all four rotations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PieceDef {
+    pub name: String,
+    /// Palette index (1-7) written into the field when the piece locks.
+    pub color: u8,
+    pub kicks: KickTable,
+    /// Side of the square the piece rotates within.
+    pub size: usize,
+    /// (x, y) offsets from the piece origin for rotation states 0, R, 2, L.
+    rotations: [Vec<(i32, i32)>; 4],
+    /// The same rotation states as row bitmasks.
+    masks: [PieceMask; 4],
+}
+
+impl PieceDef {
+    /// Build a piece from its spawn orientation drawn as a square of rows
+    /// using `X` for filled cells.  The other three states are quarter turns
+    /// clockwise about the centre of that square.
+    pub fn from_rows(name: &str, color: u8, kicks: KickTable, rows: &[&str]) -> Result<Self, String> {
+        let size = rows.len();
+        if size == 0 || rows.iter().any(|r| r.chars().count() != size) {
+            return Err(format!("piece '{}' must be drawn as a square grid", name));
+        }
+        if size > BoardSize::MAX_WIDTH {
+            return Err(format!("piece '{}' is larger than {}x{}", name, BoardSize::MAX_WIDTH, BoardSize::MAX_WIDTH));
+        }
+        let spawn: Vec<(i32, i32)> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == 'X')
+                    .map(move |(x, _)| (x as i32, y as i32))
+            })
+            .collect();
+        if spawn.is_empty() {
+            return Err(format!("piece '{}' has no cells", name));
+        }
+
+        let n = size as i32 - 1;
+        let mut rotations: [Vec<(i32, i32)>; 4] = Default::default();
+        rotations[0] = spawn;
+        for r in 1..4 {
+            rotations[r] = rotations[r - 1].iter().map(|&(x, y)| (n - y, x)).collect();
+        }
+        let masks = std::array::from_fn(|r| PieceMask::new(&rotations[r]));
+        Ok(PieceDef { name: name.to_string(), color, kicks, size, rotations, masks })
+    }
+
+    /// Check a piece read back from a file is one `from_rows` could have
+    /// built: cells inside its square and masks matching them.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=7).contains(&self.color) {
+            return Err(format!("piece '{}': color must be 1-7", self.name));
+        }
+        if !(1..=BoardSize::MAX_WIDTH).contains(&self.size) {
+            return Err(format!("piece '{}': size must be 1-{}", self.name, BoardSize::MAX_WIDTH));
+        }
+        let inside = |&(x, y): &(i32, i32)| (0..self.size as i32).contains(&x) && (0..self.size as i32).contains(&y);
+        for (cells, mask) in self.rotations.iter().zip(&self.masks) {
+            if cells.is_empty() || !cells.iter().all(inside) || *mask != PieceMask::new(cells) {
+                return Err(format!("piece '{}' is malformed", self.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn cells(&self, rotation: usize) -> &[(i32, i32)] {
+        &self.rotations[rotation % 4]
+    }
+
+    pub fn mask(&self, rotation: usize) -> &PieceMask {
+        &self.masks[rotation % 4]
+    }
+}
+
+/// The built-in piece sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Builtin {
+    Tetromino,
+    Pentomino,
+}
+
+/// The pieces a game draws from.  Piece indices used throughout the game are
+/// positions in this set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PieceSet {
+    pub name: String,
+    /// Which built-in set this is; `None` for sets loaded from a file,
+    /// whatever they are called.
+    builtin: Option<Builtin>,
+    pieces: Vec<PieceDef>,
+}
+
+impl PieceSet {
+    /// The seven guideline tetrominoes in spawn orientation, in the standard
+    /// I, S, Z, O, T, L, J order that the generators and colors assume.
+    pub fn tetrominoes() -> Self {
+        use KickTable::*;
+        let defs: [(&str, u8, KickTable, &[&str]); 7] = [
+            ("I", 1, I, &["....", "XXXX", "....", "...."]),
+            ("S", 2, Jlstz, &[".XX", "XX.", "..."]),
+            ("Z", 3, Jlstz, &["XX.", ".XX", "..."]),
+            ("O", 4, None, &["XX", "XX"]),
+            ("T", 5, Jlstz, &[".X.", "XXX", "..."]),
+            ("L", 6, Jlstz, &["..X", "XXX", "..."]),
+            ("J", 7, Jlstz, &["X..", "XXX", "..."]),
+        ];
+        Self::from_defs(Builtin::Tetromino, "tetromino", &defs)
+    }
+
+    /// The 18 one-sided pentominoes.  Kicks reuse the SRS tables.
+    pub fn pentominoes() -> Self {
+        use KickTable::*;
+        let defs: [(&str, u8, KickTable, &[&str]); 18] = [
+            ("F", 2, Jlstz, &[".XX", "XX.", ".X."]),
+            ("F'", 3, Jlstz, &["XX.", ".XX", ".X."]),
+            ("I", 1, I, &[".....", ".....", "XXXXX", ".....", "....."]),
+            ("L", 6, Jlstz, &["...X", "XXXX", "....", "...."]),
+            ("J", 7, Jlstz, &["X...", "XXXX", "....", "...."]),
+            ("N", 3, Jlstz, &["XX..", ".XXX", "....", "...."]),
+            ("N'", 2, Jlstz, &["..XX", "XXX.", "....", "...."]),
+            ("P", 4, Jlstz, &["XX.", "XX.", "X.."]),
+            ("P'", 4, Jlstz, &["XX.", "XX.", ".X."]),
+            ("T", 5, Jlstz, &["XXX", ".X.", ".X."]),
+            ("U", 6, Jlstz, &["X.X", "XXX", "..."]),
+            ("V", 7, Jlstz, &["X..", "X..", "XXX"]),
+            ("W", 1, Jlstz, &["X..", "XX.", ".XX"]),
+            ("X", 5, Jlstz, &[".X.", "XXX", ".X."]),
+            ("Y", 6, Jlstz, &[".X..", "XXXX", "....", "...."]),
+            ("Y'", 7, Jlstz, &["..X.", "XXXX", "....", "...."]),
+            ("Z", 3, Jlstz, &["XX.", ".X.", ".XX"]),
+            ("S", 2, Jlstz, &[".XX", ".X.", "XX."]),
+        ];
+        Self::from_defs(Builtin::Pentomino, "pentomino", &defs)
+    }
+
+    fn from_defs(builtin: Builtin, name: &str, defs: &[(&str, u8, KickTable, &[&str])]) -> Self {
+        let pieces = defs
+            .iter()
+            .map(|&(n, color, kicks, rows)| PieceDef::from_rows(n, color, kicks, rows).expect("built-in piece"))
+            .collect();
+        PieceSet { name: name.to_string(), builtin: Some(builtin), pieces }
+    }
+
+    /// Load a custom set from a text file.  Each piece is a header line
+    /// `NAME COLOR [KICKS]` (COLOR 1-7; KICKS one of `jlstz`, `i`, `none`)
+    /// followed by its spawn orientation as a square of `.`/`X` rows.  Pieces
+    /// are separated by blank lines; `#` starts a comment line.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut pieces = Vec::new();
+
+        let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.starts_with('#')).collect();
+        for block in lines.split(|l| l.is_empty()).filter(|b| !b.is_empty()) {
+            let mut header = block[0].split_whitespace();
+            let name = header.next().unwrap_or_default();
+            let color = header
+                .next()
+                .and_then(|c| c.parse::<u8>().ok())
+                .filter(|c| (1..=7).contains(c))
+                .ok_or_else(|| format!("piece '{}': color must be 1-7", name))?;
+            let kicks = header.next().unwrap_or("jlstz").parse()?;
+            pieces.push(PieceDef::from_rows(name, color, kicks, &block[1..])?);
+        }
+
+        if pieces.is_empty() {
+            return Err(format!("{}: no pieces defined", path.display()));
+        }
+        let name = path.file_stem().map_or("custom".into(), |s| s.to_string_lossy().into_owned());
+        Ok(PieceSet { name, builtin: None, pieces })
+    }
+
+    /// Resolve a `--pieces` argument: a built-in set name or a file path.
+    pub fn from_arg(arg: &str) -> Result<Self, String> {
+        match arg {
+            "tetromino" | "tetrominoes" => Ok(Self::tetrominoes()),
+            "pentomino" | "pentominoes" => Ok(Self::pentominoes()),
+            path => Self::load(Path::new(path)),
+        }
+    }
+
+    /// Check a set read back from a file has pieces, all well formed.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            return Err(format!("piece set '{}' is empty", self.name));
+        }
+        self.pieces.iter().try_for_each(PieceDef::validate)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    pub fn get(&self, piece: usize) -> &PieceDef {
+        &self.pieces[piece]
+    }
+
+    /// Row masks of `piece` in `rotation`.
+    pub fn mask(&self, piece: usize, rotation: usize) -> &PieceMask {
+        self.pieces[piece].mask(rotation)
+    }
+
+    /// True for the standard seven tetrominoes, whose indices some
+    /// generators rely on.
+    pub fn is_tetromino(&self) -> bool {
+        self.builtin == Some(Builtin::Tetromino)
+    }
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        Self::tetrominoes()
+    }
+}
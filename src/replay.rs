@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::{GameEvent, Subscriber};
+use crate::game::{Command, GameConfig, GameState};
+
+/// Replay file layout version, bumped when old files can no longer be read.
+const VERSION: u32 = 1;
+
+/// A command and the game time (pauses excluded) it was given at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Input {
+    pub time_ms: u64,
+    pub command: Command,
+}
+
+/// Everything needed to play a game again.  The engine is deterministic, so
+/// the settings (seed included), the clock step and the commands in order
+/// reproduce the game exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub config: GameConfig,
+    /// Size of the `step` the game was advanced by.
+    pub step_ms: u64,
+    /// Game time when the recording stopped.
+    pub duration_ms: u64,
+    pub inputs: Vec<Input>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let replay: Replay = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if replay.version != VERSION {
+            return Err(format!("{}: unsupported replay version {}", path.display(), replay.version));
+        }
+        if replay.step_ms == 0 {
+            return Err(format!("{}: step must be at least 1 ms", path.display()));
+        }
+        replay.config.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn step(&self) -> Duration {
+        Duration::from_millis(self.step_ms)
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+/// Records a game as it is played: subscribe it to the game's events and
+/// call `finish` when the game is over or abandoned.
+#[derive(Debug)]
+pub struct Recorder {
+    replay: Replay,
+}
+
+impl Recorder {
+    /// Start recording `game`, which is advanced in steps of `step`.
+    pub fn new(game: &GameState, step: Duration) -> Self {
+        let config = GameConfig { seed: Some(game.seed), ..game.config.clone() };
+        Recorder {
+            replay: Replay {
+                version: VERSION,
+                config,
+                step_ms: step.as_millis() as u64,
+                duration_ms: 0,
+                inputs: Vec::new(),
+            },
+        }
+    }
+
+    /// Carry on a recording saved with an unfinished game.
+    pub fn resume(replay: Replay) -> Self {
+        Recorder { replay }
+    }
+
+    /// The replay so far, ending at `game`'s current time.
+    pub fn snapshot(&self, game: &GameState) -> Replay {
+        Replay { duration_ms: game.stats.elapsed.as_millis() as u64, ..self.replay.clone() }
+    }
+
+    /// The finished replay, ending at `game`'s current time.
+    pub fn finish(mut self, game: &GameState) -> Replay {
+        self.replay.duration_ms = game.stats.elapsed.as_millis() as u64;
+        self.replay
+    }
+}
+
+impl Subscriber for Recorder {
+    fn on_event(&mut self, _game: &GameState, event: &GameEvent) {
+        if let GameEvent::Input { command, time } = event {
+            self.replay.inputs.push(Input { time_ms: time.as_millis() as u64, command: *command });
+        }
+    }
+}
+
+/// Plays a replay back through the engine one step at a time, with seeking.
+pub struct Player {
+    replay: Replay,
+    game: GameState,
+    /// Index of the next input to apply.
+    next: usize,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let game = GameState::new(replay.config.clone());
+        Player { replay, game, next: 0 }
+    }
+
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Game time played back so far.
+    pub fn time(&self) -> Duration {
+        self.game.stats.elapsed
+    }
+
+    /// True once every input has been applied and the game has ended or
+    /// the recording has run out.  (In practice mode an undo can take the
+    /// game back out of game over, or back in time.)
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.inputs.len() && (self.game.game_over || self.time() >= self.replay.duration())
+    }
+
+    /// Apply the inputs due now and advance one step, returning the events
+    /// it produced.
+    pub fn advance(&mut self) -> Vec<GameEvent> {
+        if self.is_finished() {
+            return Vec::new();
+        }
+        // An undo turns the clock back, so check it afresh for every input
+        while let Some(input) = self.replay.inputs.get(self.next).filter(|i| i.time_ms <= self.time().as_millis() as u64) {
+            self.game.apply(input.command);
+            self.next += 1;
+        }
+        self.game.step(self.replay.step())
+    }
+
+    /// Jump to `target` game time, replaying from the start when it lies
+    /// behind.  Events along the way are dropped.
+    pub fn seek(&mut self, target: Duration) {
+        if target < self.time() {
+            self.restart();
+        }
+        while !self.is_finished() && self.time() < target {
+            self.advance();
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.game = GameState::new(self.replay.config.clone());
+        self.next = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Bot;
+    use crate::events::dispatch;
+
+    const STEP: Duration = Duration::from_millis(16);
+
+    /// Play `config` with the AI, throwing in undos and redos every few
+    /// pieces, and return the final game with its replay.
+    fn record(config: GameConfig) -> (GameState, Replay) {
+        let mut game = GameState::new(config);
+        let mut recorder = Recorder::new(&game, STEP);
+        let mut bot = Bot::default();
+        let mut undone_at = None;
+        while game.piece_count < 20 && !game.game_over {
+            if game.piece_count % 7 == 6 && undone_at != Some(game.piece_count) {
+                undone_at = Some(game.piece_count);
+                game.apply(Command::Undo);
+                game.apply(Command::Undo);
+                game.apply(Command::Redo);
+            } else {
+                bot.step(&mut game);
+            }
+            let events = game.step(STEP);
+            dispatch(&game, &events, &mut [&mut recorder]);
+        }
+        let replay = recorder.finish(&game);
+        (game, replay)
+    }
+
+    /// Check the replay of `config`, through JSON, ends exactly where the
+    /// recorded game did; returns the replay.
+    fn assert_replays(config: GameConfig) -> Replay {
+        let (game, replay) = record(config);
+        let json = serde_json::to_string(&replay).unwrap();
+        let mut player = Player::new(serde_json::from_str(&json).unwrap());
+        while !player.is_finished() {
+            player.advance();
+        }
+        let played = player.game();
+        assert_eq!(played.score, game.score);
+        assert_eq!(played.piece_count, game.piece_count);
+        assert_eq!(played.field, game.field);
+        assert_eq!(played.stats.elapsed, game.stats.elapsed);
+        replay
+    }
+
+    #[test]
+    fn replay_reproduces_the_game() {
+        assert_replays(GameConfig { seed: Some(1), ..GameConfig::default() });
+    }
+
+    #[test]
+    fn replay_reproduces_undo_and_redo() {
+        let replay = assert_replays(GameConfig { seed: Some(2), practice: true, ..GameConfig::default() });
+        assert!(replay.inputs.iter().any(|input| input.command == Command::Undo));
+        assert!(replay.inputs.iter().any(|input| input.command == Command::Redo));
+    }
+
+    #[test]
+    fn replay_reproduces_zero_delays() {
+        assert_replays(GameConfig {
+            seed: Some(3),
+            practice: true,
+            lock_delay_ms: 0,
+            are_ms: 0,
+            line_clear_ms: 0,
+            ..GameConfig::default()
+        });
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Direction of a rotation request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RotateDir {
+    Cw,
+    Ccw,
+}
+
+impl RotateDir {
+    /// Rotation state reached by turning once from `rotation`.
+    pub fn apply(self, rotation: usize) -> usize {
+        match self {
+            RotateDir::Cw => (rotation + 1) % 4,
+            RotateDir::Ccw => (rotation + 3) % 4,
+        }
+    }
+}
+
+// SRS kick tables, indexed by [from_state][direction] where direction 0 = CW
+// and 1 = CCW.  States are 0, R, 2, L.  Offsets are (dx, dy) in field
+// coordinates, i.e. y grows downward — the guideline tables list y upward, so
+// every dy here is negated relative to the usual published tables.
+const JLSTZ_KICKS: [[[(i32, i32); 5]; 2]; 4] = [
+    [
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)], // 0 -> R
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],    // 0 -> L
+    ],
+    [
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)], // R -> 2
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)], // R -> 0
+    ],
+    [
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],    // 2 -> L
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)], // 2 -> R
+    ],
+    [
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // L -> 0
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // L -> 2
+    ],
+];
+
+const I_KICKS: [[[(i32, i32); 5]; 2]; 4] = [
+    [
+        [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)], // 0 -> R
+        [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)], // 0 -> L
+    ],
+    [
+        [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)], // R -> 2
+        [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)], // R -> 0
+    ],
+    [
+        [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)], // 2 -> L
+        [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)], // 2 -> R
+    ],
+    [
+        [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)], // L -> 0
+        [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)], // L -> 2
+    ],
+];
+
+const NO_KICKS: [(i32, i32); 1] = [(0, 0)];
+
+/// Which SRS kick table a piece uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KickTable {
+    /// J, L, S, T, Z (and most non-tetromino pieces).
+    Jlstz,
+    I,
+    /// O: rotation never moves the piece.
+    None,
+}
+
+impl FromStr for KickTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jlstz" => Ok(KickTable::Jlstz),
+            "i" => Ok(KickTable::I),
+            "none" => Ok(KickTable::None),
+            _ => Err(format!("unknown kick table '{}' (expected jlstz, i or none)", s)),
+        }
+    }
+}
+
+/// How a rotation request is resolved against the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationSystem {
+    /// Super Rotation System: guideline spawn orientations plus wall kicks.
+    #[default]
+    Srs,
+    /// Rotate in place only; give up if the new orientation collides.
+    Classic,
+}
+
+impl RotationSystem {
+    /// Offsets to try, in order, when turning a piece using `table` from
+    /// `rotation` in `dir`.
+    pub fn kicks(self, table: KickTable, rotation: usize, dir: RotateDir) -> &'static [(i32, i32)] {
+        let d = match dir {
+            RotateDir::Cw => 0,
+            RotateDir::Ccw => 1,
+        };
+        match (self, table) {
+            (RotationSystem::Classic, _) | (RotationSystem::Srs, KickTable::None) => &NO_KICKS,
+            (RotationSystem::Srs, KickTable::I) => &I_KICKS[rotation % 4][d],
+            (RotationSystem::Srs, KickTable::Jlstz) => &JLSTZ_KICKS[rotation % 4][d],
+        }
+    }
+
+    /// Resolve a rotation using `fits(rotation, x, y)` as the collision test.
+    /// Returns the new `(rotation, x, y)` and the index of the kick that
+    /// succeeded (0 = no kick), or `None` if every test collides.
+    pub fn try_rotate(
+        self,
+        table: KickTable,
+        rotation: usize,
+        x: i32,
+        y: i32,
+        dir: RotateDir,
+        fits: impl Fn(usize, i32, i32) -> bool,
+    ) -> Option<(usize, i32, i32, usize)> {
+        let new_rotation = dir.apply(rotation);
+        self.kicks(table, rotation, dir)
+            .iter()
+            .enumerate()
+            .find(|(_, &(dx, dy))| fits(new_rotation, x + dx, y + dy))
+            .map(|(i, &(dx, dy))| (new_rotation, x + dx, y + dy, i))
+    }
+}
+
+impl fmt::Display for RotationSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RotationSystem::Srs => "srs",
+            RotationSystem::Classic => "classic",
+        })
+    }
+}
+
+impl FromStr for RotationSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "srs" => Ok(RotationSystem::Srs),
+            "classic" => Ok(RotationSystem::Classic),
+            _ => Err(format!("unknown rotation system '{}' (expected srs or classic)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A rotation's from and to states with its kicks.
+    type Kicks = (usize, usize, [(i32, i32); 5]);
+
+    /// The published SRS kicks, y up, for each (from, to) rotation.
+    const JLSTZ_PUBLISHED: [Kicks; 8] = [
+        (0, 1, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+        (1, 0, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+        (1, 2, [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)]),
+        (2, 1, [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+        (2, 3, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+        (3, 2, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+        (3, 0, [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+        (0, 3, [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)]),
+    ];
+
+    const I_PUBLISHED: [Kicks; 8] = [
+        (0, 1, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+        (1, 0, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+        (1, 2, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+        (2, 1, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+        (2, 3, [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]),
+        (3, 2, [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]),
+        (3, 0, [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)]),
+        (0, 3, [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]),
+    ];
+
+    fn dir(from: usize, to: usize) -> RotateDir {
+        if RotateDir::Cw.apply(from) == to { RotateDir::Cw } else { RotateDir::Ccw }
+    }
+
+    #[test]
+    fn srs_kicks_match_the_guideline_tables() {
+        for (table, published) in [(KickTable::Jlstz, &JLSTZ_PUBLISHED), (KickTable::I, &I_PUBLISHED)] {
+            for &(from, to, kicks) in published {
+                // Field y grows downward, so every published dy flips
+                let expected: Vec<_> = kicks.iter().map(|&(dx, dy)| (dx, -dy)).collect();
+                assert_eq!(RotationSystem::Srs.kicks(table, from, dir(from, to)), expected, "{:?} {} -> {}", table, from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn o_piece_and_classic_rotation_never_kick() {
+        for rotation in 0..4 {
+            for dir in [RotateDir::Cw, RotateDir::Ccw] {
+                assert_eq!(RotationSystem::Srs.kicks(KickTable::None, rotation, dir), [(0, 0)]);
+                assert_eq!(RotationSystem::Classic.kicks(KickTable::Jlstz, rotation, dir), [(0, 0)]);
+                assert_eq!(RotationSystem::Classic.kicks(KickTable::I, rotation, dir), [(0, 0)]);
+            }
+        }
+    }
+
+    #[test]
+    fn try_rotate_takes_the_first_kick_that_fits() {
+        // Only the spot of kick 3 for 0 -> R, (0, +2) in field coordinates, is free
+        let fits = |rotation: usize, x: i32, y: i32| rotation == 1 && (x, y) == (5, 12);
+        assert_eq!(RotationSystem::Srs.try_rotate(KickTable::Jlstz, 0, 5, 10, RotateDir::Cw, fits), Some((1, 5, 12, 3)));
+        assert_eq!(RotationSystem::Classic.try_rotate(KickTable::Jlstz, 0, 5, 10, RotateDir::Cw, fits), None);
+        assert_eq!(RotationSystem::Srs.try_rotate(KickTable::Jlstz, 3, 5, 10, RotateDir::Cw, |_, _, _| true), Some((0, 5, 10, 0)));
+    }
+}
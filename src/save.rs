@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameConfig, GameState};
+use crate::replay::Replay;
+
+/// Save file layout version, bumped when old files can no longer be read.
+const VERSION: u32 = 2;
+
+/// An unfinished game as written to disk: the whole state (field, queue,
+/// RNG, score, analytics, clock) plus the replay recorded so far, so the
+/// recording carries on after a resume.  The game's settings are written
+/// once, not with it and each of its undo points.
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    version: u32,
+    config: &'a GameConfig,
+    game: &'a GameState,
+    replay: &'a Replay,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    version: u32,
+    config: GameConfig,
+    game: GameState,
+    replay: Replay,
+}
+
+/// Write `game` and its replay so far to `path`.
+pub fn save(path: &Path, game: &GameState, replay: &Replay) -> Result<(), String> {
+    let save = SaveRef { version: VERSION, config: &game.config, game, replay };
+    let text = serde_json::to_string(&save).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Read back a game written by `save`.
+pub fn load(path: &Path) -> Result<(GameState, Replay), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut file: SaveFile = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if file.version != VERSION {
+        return Err(format!("{}: unsupported save version {}", path.display(), file.version));
+    }
+    file.game.attach_config(file.config);
+    if file.game.game_over {
+        return Err(format!("{}: that game is already over", path.display()));
+    }
+    file.game
+        .validate()
+        .and_then(|()| file.replay.config.validate())
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((file.game, file.replay))
+}
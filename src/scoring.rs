@@ -0,0 +1,250 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::spin::Spin;
+
+/// What a single lock achieved, as far as scoring is concerned.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LockResult {
+    pub lines: u32,
+    pub spin: Spin,
+    /// The clear left the board completely empty.
+    pub perfect_clear: bool,
+}
+
+impl LockResult {
+    /// "Difficult" clears (Tetrises and spins that clear lines) keep a
+    /// back-to-back chain going; any other line clear breaks it.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.lines > 0 && self.spin != Spin::None)
+    }
+
+    /// Guideline points before the level multiplier and bonuses.
+    fn base_points(&self) -> u32 {
+        match (self.spin, self.lines) {
+            (Spin::None, 0) => 0,
+            (Spin::None, 1) => 100,
+            (Spin::None, 2) => 300,
+            (Spin::None, 3) => 500,
+            (Spin::None, _) => 800,
+            (Spin::Mini, 0) => 100,
+            (Spin::Mini, 1) => 200,
+            (Spin::Mini, _) => 400,
+            (Spin::Full, 0) => 400,
+            (Spin::Full, 1) => 800,
+            (Spin::Full, 2) => 1200,
+            (Spin::Full, _) => 1600,
+        }
+    }
+}
+
+/// How points are awarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoringRules {
+    /// Guideline scoring: line clears and T-spins times level, drop points,
+    /// combos, back-to-back and perfect-clear bonuses.
+    #[default]
+    Guideline,
+    /// The original rules: 25 per piece plus `2^lines * 100` per clear.
+    Classic,
+}
+
+/// Running score state: the rules plus the combo and back-to-back chains
+/// they depend on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scorer {
+    pub rules: ScoringRules,
+    /// Consecutive locks that cleared lines, minus one (`None` when the last
+    /// lock cleared nothing).
+    pub combo: Option<u32>,
+    /// The last line clear was difficult, so the next difficult one earns
+    /// the back-to-back bonus.
+    pub back_to_back: bool,
+}
+
+impl Scorer {
+    pub fn new(rules: ScoringRules) -> Self {
+        Scorer { rules, ..Scorer::default() }
+    }
+
+    /// Points for a lock at `level`, updating the combo and back-to-back
+    /// chains.
+    pub fn lock(&mut self, result: LockResult, level: u32) -> u32 {
+        let level = level.max(1);
+
+        // Locks that clear nothing break the combo but leave back-to-back alone
+        let mut b2b = false;
+        let mut combo = 0;
+        if result.lines == 0 {
+            self.combo = None;
+        } else {
+            combo = self.combo.map_or(0, |c| c + 1);
+            self.combo = Some(combo);
+            b2b = result.is_difficult() && self.back_to_back;
+            self.back_to_back = result.is_difficult();
+        }
+
+        match self.rules {
+            ScoringRules::Guideline => {
+                // Back-to-back difficult clears are worth half as much again.
+                // The arithmetic saturates so a huge level or combo caps the
+                // points instead of overflowing.
+                let base = result.base_points();
+                let base = if b2b { base * 3 / 2 } else { base };
+                let mut points = base.saturating_mul(level);
+                points = points.saturating_add(50u32.saturating_mul(level).saturating_mul(combo));
+                if result.perfect_clear {
+                    let bonus: u32 = match result.lines {
+                        1 => 800,
+                        2 => 1200,
+                        3 => 1800,
+                        _ if b2b => 3200,
+                        _ => 2000,
+                    };
+                    points = points.saturating_add(bonus.saturating_mul(level));
+                }
+                points
+            }
+            ScoringRules::Classic if result.lines > 0 => 25 + (1u32 << result.lines) * 100,
+            ScoringRules::Classic => 25,
+        }
+    }
+
+    /// Points for moving the piece down `rows` with soft drop.
+    pub fn soft_drop(&self, rows: u32) -> u32 {
+        match self.rules {
+            ScoringRules::Guideline => rows,
+            ScoringRules::Classic => 0,
+        }
+    }
+
+    /// Points for a hard drop of `rows`.
+    pub fn hard_drop(&self, rows: u32) -> u32 {
+        match self.rules {
+            ScoringRules::Guideline => 2 * rows,
+            ScoringRules::Classic => 0,
+        }
+    }
+}
+
+impl fmt::Display for ScoringRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScoringRules::Guideline => "guideline",
+            ScoringRules::Classic => "classic",
+        })
+    }
+}
+
+impl FromStr for ScoringRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guideline" => Ok(ScoringRules::Guideline),
+            "classic" => Ok(ScoringRules::Classic),
+            _ => Err(format!("unknown scoring rules '{}' (expected guideline or classic)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u32, spin: Spin) -> LockResult {
+        LockResult { lines, spin, perfect_clear: false }
+    }
+
+    fn perfect(lines: u32) -> LockResult {
+        LockResult { lines, spin: Spin::None, perfect_clear: true }
+    }
+
+    /// Points for `result` as the first lock of a game at `level`.
+    fn fresh(rules: ScoringRules, result: LockResult, level: u32) -> u32 {
+        Scorer::new(rules).lock(result, level)
+    }
+
+    #[test]
+    fn guideline_base_points_times_level() {
+        let cases = [
+            (clear(0, Spin::None), 1, 0),
+            (clear(1, Spin::None), 1, 100),
+            (clear(2, Spin::None), 1, 300),
+            (clear(3, Spin::None), 1, 500),
+            (clear(4, Spin::None), 1, 800),
+            (clear(1, Spin::None), 3, 300),
+            (clear(4, Spin::None), 5, 4000),
+            (clear(0, Spin::Mini), 1, 100),
+            (clear(1, Spin::Mini), 1, 200),
+            (clear(2, Spin::Mini), 1, 400),
+            (clear(0, Spin::Full), 1, 400),
+            (clear(1, Spin::Full), 1, 800),
+            (clear(2, Spin::Full), 2, 2400),
+            (clear(3, Spin::Full), 1, 1600),
+            // Level 0 scores as level 1
+            (clear(1, Spin::None), 0, 100),
+        ];
+        for (result, level, expected) in cases {
+            assert_eq!(fresh(ScoringRules::Guideline, result, level), expected, "{:?} at level {}", result, level);
+        }
+    }
+
+    #[test]
+    fn guideline_combo_and_back_to_back() {
+        let mut scorer = Scorer::new(ScoringRules::Guideline);
+        let cases = [
+            (clear(4, Spin::None), 1600),
+            // Back-to-back TSD, combo 1
+            (clear(2, Spin::Full), 1800 * 2 + 50 * 2),
+            // A single breaks back-to-back but not the combo
+            (clear(1, Spin::None), 100 * 2 + 50 * 2 * 2),
+            (clear(4, Spin::None), 800 * 2 + 50 * 2 * 3),
+            // Clearing nothing ends the combo but keeps back-to-back
+            (clear(0, Spin::None), 0),
+            (clear(4, Spin::None), 1200 * 2),
+        ];
+        for (i, (result, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(scorer.lock(result, 2), expected, "lock {}", i);
+        }
+    }
+
+    #[test]
+    fn guideline_perfect_clears() {
+        assert_eq!(fresh(ScoringRules::Guideline, perfect(1), 1), 100 + 800);
+        assert_eq!(fresh(ScoringRules::Guideline, perfect(2), 1), 300 + 1200);
+        assert_eq!(fresh(ScoringRules::Guideline, perfect(3), 1), 500 + 1800);
+        assert_eq!(fresh(ScoringRules::Guideline, perfect(4), 2), (800 + 2000) * 2);
+        let mut scorer = Scorer { back_to_back: true, ..Scorer::new(ScoringRules::Guideline) };
+        assert_eq!(scorer.lock(perfect(4), 1), 1200 + 3200);
+    }
+
+    #[test]
+    fn guideline_points_saturate() {
+        assert_eq!(fresh(ScoringRules::Guideline, clear(4, Spin::None), u32::MAX), u32::MAX);
+        let mut scorer = Scorer { combo: Some(u32::MAX - 1), ..Scorer::new(ScoringRules::Guideline) };
+        assert_eq!(scorer.lock(clear(1, Spin::None), 1), u32::MAX);
+    }
+
+    #[test]
+    fn classic_points_ignore_level_and_bonuses() {
+        let cases = [(0, 25), (1, 225), (2, 425), (3, 825), (4, 1625)];
+        for (lines, expected) in cases {
+            for level in [1, 10] {
+                assert_eq!(fresh(ScoringRules::Classic, clear(lines, Spin::Full), level), expected);
+            }
+        }
+        assert_eq!(fresh(ScoringRules::Classic, perfect(4), 1), 1625);
+    }
+
+    #[test]
+    fn drop_points() {
+        let guideline = Scorer::new(ScoringRules::Guideline);
+        let classic = Scorer::new(ScoringRules::Classic);
+        assert_eq!((guideline.soft_drop(5), guideline.hard_drop(5)), (5, 10));
+        assert_eq!((classic.soft_drop(5), classic.hard_drop(5)), (0, 0));
+    }
+}
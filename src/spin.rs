@@ -0,0 +1,83 @@
+/// Spin recognised when a piece locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Spin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+/// Corners of the T's 3×3 box as (x, y) offsets from the piece origin, and
+/// for each rotation state (0, R, 2, L) the two corners on the side the T
+/// points towards.
+const T_CORNERS: [(i32, i32); 4] = [(0, 0), (2, 0), (0, 2), (2, 2)];
+const T_FRONT: [[(i32, i32); 2]; 4] = [
+    [(0, 0), (2, 0)], // 0: points up
+    [(2, 0), (2, 2)], // R: points right
+    [(0, 2), (2, 2)], // 2: points down
+    [(0, 0), (0, 2)], // L: points left
+];
+
+/// Index of the SRS kick test whose (±1, ±2) offset always yields a full
+/// T-spin, even when only one front corner is filled.
+const TST_KICK: usize = 4;
+
+/// Classify a T lock with the 3-corner rule.  `occupied(x, y)` tells
+/// whether the cell at that offset from the piece origin is filled or off
+/// the board; `kick` is the kick test used by the rotation that put the T
+/// there.  The caller must already know the last action was a rotation.
+pub fn t_spin(rotation: usize, kick: usize, occupied: impl Fn(i32, i32) -> bool) -> Spin {
+    let corners = T_CORNERS.iter().filter(|&&(x, y)| occupied(x, y)).count();
+    if corners < 3 {
+        return Spin::None;
+    }
+    let front = T_FRONT[rotation % 4].iter().filter(|&&(x, y)| occupied(x, y)).count();
+    if front == 2 || kick == TST_KICK {
+        Spin::Full
+    } else {
+        Spin::Mini
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TL: (i32, i32) = (0, 0);
+    const TR: (i32, i32) = (2, 0);
+    const BL: (i32, i32) = (0, 2);
+    const BR: (i32, i32) = (2, 2);
+
+    /// Classify a T whose box has just the `filled` corners occupied.
+    fn spin(rotation: usize, kick: usize, filled: &[(i32, i32)]) -> Spin {
+        t_spin(rotation, kick, |x, y| filled.contains(&(x, y)))
+    }
+
+    #[test]
+    fn three_corner_rule() {
+        assert_eq!(spin(0, 0, &[TL, TR]), Spin::None);
+        assert_eq!(spin(0, 0, &[BL, BR]), Spin::None);
+        assert_eq!(spin(0, 0, &[TL, TR, BL]), Spin::Full);
+        assert_eq!(spin(0, 0, &[TL, BL, BR]), Spin::Mini);
+        assert_eq!(spin(0, 0, &[TL, TR, BL, BR]), Spin::Full);
+        assert_eq!(spin(1, 0, &[TR, BR, BL]), Spin::Full);
+        assert_eq!(spin(1, 0, &[TL, BL, BR]), Spin::Mini);
+        assert_eq!(spin(2, 0, &[BL, BR, TR]), Spin::Full);
+        assert_eq!(spin(3, 0, &[TL, TR, BR]), Spin::Mini);
+        assert_eq!(spin(3, 0, &[TL, BL, BR]), Spin::Full);
+    }
+
+    #[test]
+    fn tst_kick_upgrades_a_mini() {
+        // The (±1, ±2) kick always counts as a full spin
+        assert_eq!(spin(0, TST_KICK, &[TL, BL, BR]), Spin::Full);
+        assert_eq!(spin(0, TST_KICK - 1, &[TL, BL, BR]), Spin::Mini);
+        assert_eq!(spin(0, TST_KICK, &[BL, BR]), Spin::None);
+    }
+
+    #[test]
+    fn the_centre_and_edges_dont_count() {
+        // Only the four corners of the box matter, not the cells beside the T
+        assert_eq!(t_spin(0, 0, |x, y| (x, y) != TL && (x, y) != TR), Spin::None);
+    }
+}
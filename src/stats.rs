@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::spin::Spin;
+
+/// How many recent pieces `Stats::lines_history` remembers.
+pub const HISTORY_LEN: usize = 20;
+
+/// Per-game analytics: clear counts, recent history and play time.  The
+/// rules never read these; they are kept for the analytics panel and logs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub lines_cleared: u32,
+    pub singles: u32,
+    pub doubles: u32,
+    pub triples: u32,
+    pub tetrises: u32,
+    /// Line clears made with a spin; these are not counted in 1L-4L.
+    pub spin_clears: u32,
+    pub perfect_clears: u32,
+    /// Lines cleared by each of the last `HISTORY_LEN` pieces.
+    pub lines_history: VecDeque<u8>,
+    /// Game time played, excluding pauses.
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    /// Update the counts from one event of the game's stream.
+    pub fn record(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Locked { placement } => self.record_lock(placement.lines, placement.spin != Spin::None),
+            GameEvent::LinesCleared { kind, .. } if kind.perfect_clear => self.perfect_clears += 1,
+            _ => {}
+        }
+    }
+
+    /// Record a lock that cleared `lines`, `spin` telling whether it was a
+    /// spin clear.
+    pub fn record_lock(&mut self, lines: u32, spin: bool) {
+        self.lines_cleared += lines;
+        match lines {
+            1.. if spin => self.spin_clears += 1,
+            1 => self.singles += 1,
+            2 => self.doubles += 1,
+            3 => self.triples += 1,
+            4.. => self.tetrises += 1,
+            _ => {}
+        }
+        if self.lines_history.len() >= HISTORY_LEN {
+            self.lines_history.pop_front();
+        }
+        self.lines_history.push_back(lines as u8);
+    }
+
+    /// Line clears of every kind.
+    pub fn total_clears(&self) -> u32 {
+        self.singles + self.doubles + self.triples + self.tetrises + self.spin_clears
+    }
+}
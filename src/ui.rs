@@ -1,16 +1,27 @@
+use std::time::Duration;
+
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Widget},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
     Frame,
 };
 use tui_piechart::{PieChart, PieSlice};
 
-use crate::game::{BoardStats, GameState, FIELD_HEIGHT, FIELD_WIDTH, TETROMINOES};
+use crate::highscores::{Entry, TABLE_SIZE};
+use tetris::events::{GameEvent, Subscriber};
+use tetris::game::{BoardStats, GameState};
+use tetris::spin::Spin;
+
+/// How long a callout stays on screen.
+const CALLOUT_TIME: Duration = Duration::from_millis(1500);
+/// How long the board flashes after a perfect clear.
+const PERFECT_CLEAR_FLASH: Duration = Duration::from_millis(1500);
 
-/// Map piece index (1-7) to a color. 0 = empty, 9 = border, 8 = cleared flash.
+/// Map a palette index (1-7) to a color. 0 = empty, 9 = border, 8 = cleared
+/// flash.  Tetrominoes use one index each, in the order listed.
 fn piece_color(val: u8) -> Color {
     match val {
         1 => Color::Cyan,    // I
@@ -32,52 +43,52 @@ struct BoardWidget<'a> {
 
 impl<'a> Widget for BoardWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        // Build a display buffer: copy field
-        let mut display = self.game.field;
-        // 0=empty, 1-7=locked piece, 8=cleared, 9=border
-        // 10-16 = ghost piece (piece_index+10), rendered as outline
-
-        if !self.game.game_over {
-            let tetromino = TETROMINOES[self.game.current_piece];
+        // Build a display buffer: copy the field's colors
+        let field = &self.game.field;
+        let mut display: Vec<Vec<u8>> = field.rows().map(<[u8]>::to_vec).collect();
+        let (width, height) = (field.width() as i32, field.height() as i32);
+        // 0=empty, 1-7=locked piece color, 8=cleared, 9=border
+        // 10-16 = ghost piece (color+9), rendered as outline
+        // 20-26 = active piece resting on the stack (color+19), shaded by lock timer
+
+        // No piece in play during the line-clear and entry delays
+        if self.game.piece_active() {
+            let def = self.game.config.pieces.get(self.game.current_piece);
+            let cells = def.cells(self.game.current_rotation);
             let ghost_y = self.game.ghost_drop_y();
 
             // Draw ghost first (underneath active piece)
-            for px in 0..4usize {
-                for py in 0..4usize {
-                    let pi = GameState::rotate(px, py, self.game.current_rotation);
-                    if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                        let fx = self.game.current_x + px as i32;
-                        let fy = ghost_y + py as i32;
-                        if fx >= 0 && fx < FIELD_WIDTH as i32 && fy >= 0 && fy < FIELD_HEIGHT as i32 {
-                            // Only draw ghost where the field is empty
-                            if display[fy as usize][fx as usize] == 0 {
-                                display[fy as usize][fx as usize] =
-                                    (self.game.current_piece + 10) as u8; // ghost marker
-                            }
-                        }
+            for &(px, py) in cells {
+                let fx = self.game.current_x + px;
+                let fy = ghost_y + py;
+                if fx >= 0 && fx < width && fy >= 0 && fy < height {
+                    // Only draw ghost where the field is empty
+                    if display[fy as usize][fx as usize] == 0 {
+                        display[fy as usize][fx as usize] = def.color + 9; // ghost marker
                     }
                 }
             }
 
-            // Draw active piece on top
-            for px in 0..4usize {
-                for py in 0..4usize {
-                    let pi = GameState::rotate(px, py, self.game.current_rotation);
-                    if tetromino.chars().nth(pi).unwrap_or('.') == 'X' {
-                        let fx = self.game.current_x + px as i32;
-                        let fy = self.game.current_y + py as i32;
-                        if fx >= 0 && fx < FIELD_WIDTH as i32 && fy >= 0 && fy < FIELD_HEIGHT as i32 {
-                            display[fy as usize][fx as usize] =
-                                (self.game.current_piece + 1) as u8;
-                        }
-                    }
+            // Draw active piece on top, marked while its lock timer runs
+            let marker = if self.game.lock_timer.is_some() { def.color + 19 } else { def.color };
+            for &(px, py) in cells {
+                let fx = self.game.current_x + px;
+                let fy = self.game.current_y + py;
+                if fx >= 0 && fx < width && fy >= 0 && fy < height {
+                    display[fy as usize][fx as usize] = marker;
                 }
             }
         }
 
+        // Only the visible rows and the floor are drawn; the buffer zone stays
+        // hidden.  If the area is too short, the top rows are cut off so the
+        // stack stays in view.
+        let visible = field.height() - self.game.config.board.buffer;
+        let skip = self.game.config.board.buffer + visible.saturating_sub(area.height as usize);
+
         // Render cell-by-cell; each cell is 2 chars wide
-        for row in 0..FIELD_HEIGHT {
-            for col in 0..FIELD_WIDTH {
+        for (row, cells) in display.iter().skip(skip).enumerate() {
+            for (col, &val) in cells.iter().enumerate() {
                 let cell_x = area.x + (col as u16) * 2;
                 let cell_y = area.y + row as u16;
 
@@ -85,13 +96,34 @@ impl<'a> Widget for BoardWidget<'a> {
                     continue;
                 }
 
-                let val = display[row][col];
-                let (fg, bg, ch) = if val >= 10 {
+                let (fg, bg, ch) = if val >= 20 {
+                    // Grounded piece: fades as the lock delay runs out
+                    let color = piece_color(val - 19);
+                    let ch = match self.game.lock_progress().unwrap_or(0.0) {
+                        p if p < 0.33 => '█',
+                        p if p < 0.66 => '▓',
+                        _ => '▒',
+                    };
+                    (color, Color::Reset, ch)
+                } else if val >= 10 {
                     // Ghost piece: dim outline using piece color, no background fill
                     let color = piece_color(val - 9);
                     (color, Color::Reset, '░')
                 } else if val == 0 {
                     (Color::DarkGray, Color::Reset, '·')
+                } else if val == 8 {
+                    // Cleared row: wiped away from the centre outwards
+                    let progress = self.game.line_clear_progress().unwrap_or(1.0);
+                    let centre = (width as f32 - 1.0) / 2.0;
+                    let reach = progress * centre;
+                    let dist = (col as f32 - centre).abs();
+                    if dist < reach {
+                        (Color::DarkGray, Color::Reset, '·')
+                    } else if dist < reach + 1.0 {
+                        (Color::White, Color::Reset, '▓')
+                    } else {
+                        (Color::White, Color::White, '█')
+                    }
                 } else {
                     let color = piece_color(val);
                     (color, color, '█')
@@ -105,17 +137,27 @@ impl<'a> Widget for BoardWidget<'a> {
     }
 }
 
-fn render_next_piece(game: &GameState) -> Vec<Line<'static>> {
-    let tetromino = TETROMINOES[game.next_piece];
-    let color = piece_color((game.next_piece + 1) as u8);
+/// Spawn-orientation preview of `piece` for the Next and Hold panels,
+/// optionally headed by its name.  `dimmed` greys it out (hold already used).
+fn render_piece_preview(game: &GameState, piece: usize, dimmed: bool, named: bool) -> Vec<Line<'static>> {
+    let def = game.config.pieces.get(piece);
+    let cells = def.cells(0);
+    let color = if dimmed { Color::DarkGray } else { piece_color(def.color) };
     let mut lines = Vec::new();
+    if named {
+        lines.push(Line::from(Span::styled(def.name.clone(), Style::default().fg(color))));
+    }
 
-    for py in 0..4usize {
+    // Draw only the bounding box of the spawn orientation
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let max_x = cells.iter().map(|c| c.0).max().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let max_y = cells.iter().map(|c| c.1).max().unwrap_or(0);
+
+    for py in min_y..=max_y {
         let mut spans = Vec::new();
-        for px in 0..4usize {
-            let pi = py * 4 + px;
-            let ch = tetromino.chars().nth(pi).unwrap_or('.');
-            if ch == 'X' {
+        for px in min_x..=max_x {
+            if cells.contains(&(px, py)) {
                 spans.push(Span::styled("██", Style::default().fg(color)));
             } else {
                 spans.push(Span::raw("  "));
@@ -160,27 +202,27 @@ fn render_analytics(f: &mut Frame, game: &GameState, area: Rect) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(5), // Board Health
-            Constraint::Length(7), // Lines
+            Constraint::Length(9), // Lines
             Constraint::Length(5), // Efficiency
             Constraint::Length(3), // Trend sparkline
             Constraint::Min(5),    // Pie chart (uses remaining space)
         ])
         .split(area);
 
-    render_board_health(f, &stats, chunks[0]);
+    render_board_health(f, &stats, game.config.board.height as i32, chunks[0]);
     render_lines(f, game, chunks[1]);
     render_efficiency(f, game, chunks[2]);
     render_trend(f, game, chunks[3]);
     render_clears_pie(f, game, chunks[4]);
 }
 
-fn render_board_health(f: &mut Frame, stats: &BoardStats, area: Rect) {
-    let ht_color = traffic_light(stats.max_height, 8, 12);
+fn render_board_health(f: &mut Frame, stats: &BoardStats, rows: i32, area: Rect) {
+    let ht_color = traffic_light(stats.max_height, rows / 2, rows - 5);
     let holes_color = traffic_light(stats.holes as i32, 2, 5);
     let bumpy_color = traffic_light(stats.bumpiness, 5, 10);
 
-    // 12-block bar relative to max playfield height (16)
-    let bar_fill = ((stats.max_height as usize) * 12 / 16).min(12);
+    // 12-block bar relative to the visible height
+    let bar_fill = ((stats.max_height.max(0) as usize) * 12 / rows.max(1) as usize).min(12);
     let bar = format!("{}{}", "█".repeat(bar_fill), "░".repeat(12 - bar_fill));
 
     let text = Text::from(vec![
@@ -207,19 +249,15 @@ fn render_board_health(f: &mut Frame, stats: &BoardStats, area: Rect) {
 }
 
 fn render_lines(f: &mut Frame, game: &GameState, area: Rect) {
-    let max_type = [game.singles, game.doubles, game.triples, game.tetrises]
+    let max_type = [game.stats.singles, game.stats.doubles, game.stats.triples, game.stats.tetrises, game.stats.spin_clears]
         .iter()
         .copied()
         .max()
         .unwrap_or(1)
         .max(1);
 
-    let total_clears = game.singles + game.doubles + game.triples + game.tetrises;
-    let tetris_pct = if total_clears > 0 {
-        game.tetrises * 100 / total_clears
-    } else {
-        0
-    };
+    let total_clears = game.stats.total_clears();
+    let tetris_pct = (game.stats.tetrises * 100).checked_div(total_clears).unwrap_or(0);
     let tetris_color = if tetris_pct >= 20 {
         Color::Cyan
     } else if tetris_pct >= 10 {
@@ -241,14 +279,22 @@ fn render_lines(f: &mut Frame, game: &GameState, area: Rect) {
         Line::from(vec![
             Span::raw(" Total  "),
             Span::styled(
-                format!("{:>15}", game.lines_cleared),
+                format!("{:>15}", game.stats.lines_cleared),
                 Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
             ),
         ]),
-        make_row("1L", game.singles, Color::White),
-        make_row("2L", game.doubles, Color::Yellow),
-        make_row("3L", game.triples, Color::Green),
-        make_row("4L", game.tetrises, Color::Cyan),
+        make_row("1L", game.stats.singles, Color::White),
+        make_row("2L", game.stats.doubles, Color::Yellow),
+        make_row("3L", game.stats.triples, Color::Green),
+        make_row("4L", game.stats.tetrises, Color::Cyan),
+        make_row("SP", game.stats.spin_clears, Color::Magenta),
+        Line::from(vec![
+            Span::raw(" Perfect"),
+            Span::styled(
+                format!("{:>15}", game.stats.perfect_clears),
+                Style::default().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            ),
+        ]),
         Line::from(vec![
             Span::raw(" Tetris%"),
             Span::styled(
@@ -266,17 +312,13 @@ fn render_lines(f: &mut Frame, game: &GameState, area: Rect) {
 }
 
 fn render_efficiency(f: &mut Frame, game: &GameState, area: Rect) {
-    let score_per_pc = if game.piece_count > 0 {
-        game.score / game.piece_count
-    } else {
-        0
-    };
+    let score_per_pc = game.score.checked_div(game.piece_count).unwrap_or(0);
     let lines_per_pc = if game.piece_count > 0 {
-        game.lines_cleared as f32 / game.piece_count as f32
+        game.stats.lines_cleared as f32 / game.piece_count as f32
     } else {
         0.0
     };
-    let elapsed = game.start_time.elapsed().as_secs_f32();
+    let elapsed = game.stats.elapsed.as_secs_f32();
     let pcs_per_sec = if elapsed > 0.5 {
         game.piece_count as f32 / elapsed
     } else {
@@ -327,7 +369,7 @@ fn render_trend(f: &mut Frame, game: &GameState, area: Rect) {
     let bar_colors = [Color::DarkGray, Color::White, Color::Yellow, Color::Green, Color::Cyan];
 
     let mut spans: Vec<Span> = vec![Span::raw(" ")];
-    for &n in &game.lines_history {
+    for &n in &game.stats.lines_history {
         let idx = (n as usize).min(4);
         spans.push(Span::styled(
             bar_chars[idx].to_string(),
@@ -335,7 +377,7 @@ fn render_trend(f: &mut Frame, game: &GameState, area: Rect) {
         ));
     }
     // pad to fill width
-    let filled = game.lines_history.len() + 1;
+    let filled = game.stats.lines_history.len() + 1;
     let inner_w = area.width.saturating_sub(2) as usize;
     if filled < inner_w {
         spans.push(Span::styled(
@@ -361,7 +403,7 @@ fn render_clears_pie(f: &mut Frame, game: &GameState, area: Rect) {
         ))
         .border_style(Style::default().fg(Color::LightBlue));
 
-    let total = game.singles + game.doubles + game.triples + game.tetrises;
+    let total = game.stats.total_clears();
     if total == 0 {
         // No data yet — show placeholder
         let text = Text::from(vec![Line::from(Span::styled(
@@ -373,10 +415,11 @@ fn render_clears_pie(f: &mut Frame, game: &GameState, area: Rect) {
     }
 
     let slices = vec![
-        PieSlice::new("1L", game.singles as f64, Color::White),
-        PieSlice::new("2L", game.doubles as f64, Color::Yellow),
-        PieSlice::new("3L", game.triples as f64, Color::Green),
-        PieSlice::new("4L", game.tetrises as f64, Color::Cyan),
+        PieSlice::new("1L", game.stats.singles as f64, Color::White),
+        PieSlice::new("2L", game.stats.doubles as f64, Color::Yellow),
+        PieSlice::new("3L", game.stats.triples as f64, Color::Green),
+        PieSlice::new("4L", game.stats.tetrises as f64, Color::Cyan),
+        PieSlice::new("Spin", game.stats.spin_clears as f64, Color::Magenta),
     ];
 
     let chart = PieChart::new(slices)
@@ -387,13 +430,120 @@ fn render_clears_pie(f: &mut Frame, game: &GameState, area: Rect) {
     f.render_widget(chart, area);
 }
 
-pub fn render_ui(f: &mut Frame, game: &GameState) {
+/// Playback position shown while watching a replay.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayStatus {
+    pub time: Duration,
+    pub duration: Duration,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+/// Name being typed for a score that made the high-score table.
+#[derive(Debug, Clone)]
+pub struct NameEntry {
+    /// Place on the table, from 0.
+    pub rank: usize,
+    pub name: String,
+    /// The entry has been written to the table.
+    pub saved: bool,
+}
+
+/// What the front end is doing around the game, shown in the sidebar and
+/// the game-over box.
+#[derive(Debug, Clone, Copy)]
+pub enum Hud<'a> {
+    Play { ai_mode: bool, name_entry: Option<&'a NameEntry> },
+    Replay(ReplayStatus),
+}
+
+/// Transient on-board effects, driven by the game's event stream: clear
+/// callouts and the perfect-clear flash.
+#[derive(Debug, Default)]
+pub struct Effects {
+    /// Callout text and how long it stays up.
+    callout: Option<(String, Duration)>,
+    /// Time left on the perfect-clear flash, if one is showing.
+    perfect_clear_flash: Option<Duration>,
+    /// Piece of the last lock, which names any spin in the clear after it.
+    last_piece: usize,
+}
+
+impl Effects {
+    /// Run the effect timers; call while the game is running.
+    pub fn update(&mut self, dt: Duration) {
+        if let Some((_, remaining)) = &mut self.callout {
+            *remaining = remaining.saturating_sub(dt);
+            if remaining.is_zero() {
+                self.callout = None;
+            }
+        }
+        if let Some(flash) = self.perfect_clear_flash {
+            self.perfect_clear_flash = Some(flash.saturating_sub(dt)).filter(|t| !t.is_zero());
+        }
+    }
+
+    /// Flash a message over the board, the way clears are announced.
+    pub fn notify(&mut self, text: &str) {
+        self.callout = Some((text.to_string(), CALLOUT_TIME));
+    }
+
+    fn show_callout(&mut self, text: Option<String>) {
+        if let Some(text) = text {
+            self.callout = Some((text, CALLOUT_TIME));
+        }
+    }
+}
+
+impl Subscriber for Effects {
+    fn on_event(&mut self, game: &GameState, event: &GameEvent) {
+        match event {
+            GameEvent::Locked { placement } => {
+                self.last_piece = placement.piece;
+                if placement.lines == 0 {
+                    let name = &game.config.pieces.get(placement.piece).name;
+                    self.show_callout(callout_text(name, placement.spin, 0, false));
+                }
+            }
+            GameEvent::LinesCleared { rows, kind } => {
+                let name = &game.config.pieces.get(self.last_piece).name;
+                self.show_callout(callout_text(name, kind.spin, rows.len(), kind.back_to_back));
+                if kind.perfect_clear {
+                    self.perfect_clear_flash = Some(PERFECT_CLEAR_FLASH);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Callout for a lock worth announcing ("T-SPIN DOUBLE", "B2B TETRIS"), if
+/// any.  `piece` names the spin.
+fn callout_text(piece: &str, spin: Spin, lines: usize, b2b: bool) -> Option<String> {
+    let suffix = match lines {
+        0 => "",
+        1 => " SINGLE",
+        2 => " DOUBLE",
+        3 => " TRIPLE",
+        _ => " TETRIS",
+    };
+    let text = match spin {
+        Spin::Full => format!("{}-SPIN{}", piece, suffix),
+        Spin::Mini => format!("MINI {}-SPIN{}", piece, suffix),
+        Spin::None if lines >= 4 => "TETRIS".to_string(),
+        Spin::None => return None,
+    };
+    Some(if b2b { format!("B2B {}", text) } else { text })
+}
+
+pub fn render_ui(f: &mut Frame, game: &GameState, effects: &Effects, hud: &Hud<'_>) {
     let size = f.area();
 
-    // Column widths
-    let analytics_width: u16 = 46;
-    let board_width = (FIELD_WIDTH as u16) * 2 + 2;
-    let sidebar_width: u16 = 22;
+    // Column widths; the analytics panel is dropped when the terminal is too
+    // narrow for it next to the board
+    let board_width = (game.field.width() as u16) * 2 + 2;
+    let sidebar_width: u16 = 24;
+    let analytics_width: u16 = if size.width >= 46 + board_width + sidebar_width { 46 } else { 0 };
     let total_width = analytics_width + board_width + sidebar_width;
 
     // Center the game horizontally by adding equal padding on both sides
@@ -417,7 +567,9 @@ pub fn render_ui(f: &mut Frame, game: &GameState) {
         .split(center);
 
     // --- Analytics ---
-    render_analytics(f, game, chunks[0]);
+    if analytics_width > 0 {
+        render_analytics(f, game, chunks[0]);
+    }
 
     // --- Board ---
     let board_block = Block::default()
@@ -426,67 +578,123 @@ pub fn render_ui(f: &mut Frame, game: &GameState) {
     let inner_board = board_block.inner(chunks[1]);
     f.render_widget(board_block, chunks[1]);
     f.render_widget(BoardWidget { game }, inner_board);
+    if let Some((text, _)) = &effects.callout {
+        render_callout(f, inner_board, text);
+    }
+    if let Some(remaining) = effects.perfect_clear_flash {
+        render_perfect_clear(f, inner_board, remaining);
+    }
 
     // --- Sidebar ---
+    // Next queue: the front piece is named, the rest are separated by a gap
+    let mut next_lines = Vec::new();
+    for (i, &piece) in game.next_queue.iter().enumerate() {
+        if i > 0 {
+            next_lines.push(Line::from(""));
+        }
+        next_lines.extend(render_piece_preview(game, piece, false, i == 0));
+    }
+
     let sidebar_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),  // Score
-            Constraint::Length(3),  // AI status
-            Constraint::Length(6),  // Next piece
+            Constraint::Length(5),  // Score / level / chains
+            Constraint::Length(if let Hud::Replay(_) = hud { 4 } else { 3 }), // AI / replay status
+            Constraint::Length(next_lines.len().max(4) as u16 + 2), // Hold / Next queue
             Constraint::Min(0),     // Controls
         ])
         .split(chunks[2]);
 
     // Score
-    let score_text = Text::from(vec![Line::from(format!("Score: {}", game.score))]);
+    let score_text = Text::from(vec![
+        Line::from(format!("Score: {}", game.score)),
+        Line::from(format!("Level: {}", game.level)),
+        render_chains(game),
+    ]);
+    let score_title = if game.config.practice { " Score (practice) " } else { " Score " };
     let score_widget = Paragraph::new(score_text)
-        .block(Block::default().borders(Borders::ALL).title(" Score "));
+        .block(Block::default().borders(Borders::ALL).title(score_title));
     f.render_widget(score_widget, sidebar_chunks[0]);
 
-    // AI status badge
-    let (ai_label, ai_style) = if game.ai_mode {
-        (
-            "▶ AI: ON ",
-            Style::default().fg(Color::Green),
-        )
-    } else {
-        (
-            "  AI: OFF",
-            Style::default().fg(Color::DarkGray),
-        )
+    // AI status badge, or the playback position during a replay
+    match hud {
+        Hud::Play { ai_mode, .. } => {
+            let (ai_label, ai_style) = if *ai_mode {
+                (
+                    "▶ AI: ON ",
+                    Style::default().fg(Color::Green),
+                )
+            } else {
+                (
+                    "  AI: OFF",
+                    Style::default().fg(Color::DarkGray),
+                )
+            };
+            let ai_text = Text::from(vec![Line::from(Span::styled(ai_label, ai_style))]);
+            let ai_widget = Paragraph::new(ai_text)
+                .block(Block::default().borders(Borders::ALL).title(" AI "));
+            f.render_widget(ai_widget, sidebar_chunks[1]);
+        }
+        Hud::Replay(status) => render_replay_status(f, status, sidebar_chunks[1]),
+    }
+
+    // Hold slot and next queue, side by side
+    let preview_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(sidebar_chunks[2]);
+
+    let hold_lines = match game.hold_piece {
+        Some(piece) => render_piece_preview(game, piece, game.hold_used, true),
+        None => vec![Line::from(Span::styled("empty", Style::default().fg(Color::DarkGray)))],
     };
-    let ai_text = Text::from(vec![Line::from(Span::styled(ai_label, ai_style))]);
-    let ai_widget = Paragraph::new(ai_text)
-        .block(Block::default().borders(Borders::ALL).title(" AI "));
-    f.render_widget(ai_widget, sidebar_chunks[1]);
-
-    // Next piece preview
-    let mut next_lines = vec![Line::from("Next:")];
-    next_lines.extend(render_next_piece(game));
+    let hold_widget = Paragraph::new(Text::from(hold_lines))
+        .block(Block::default().borders(Borders::ALL).title(" Hold "));
+    f.render_widget(hold_widget, preview_chunks[0]);
+
     let next_widget = Paragraph::new(Text::from(next_lines))
         .block(Block::default().borders(Borders::ALL).title(" Next "));
-    f.render_widget(next_widget, sidebar_chunks[2]);
+    f.render_widget(next_widget, preview_chunks[1]);
 
     // Controls
-    let controls_text = Text::from(vec![
-        Line::from("Controls:"),
-        Line::from(""),
-        Line::from("← →  Move"),
-        Line::from("↑    Rotate"),
-        Line::from("↓    Soft drop"),
-        Line::from("Spc  Hard drop"),
-        Line::from("p    Pause"),
-        Line::from("a    AI mode"),
-        Line::from("q    Quit"),
-    ]);
+    let controls_text = if let Hud::Replay(_) = hud {
+        Text::from(vec![
+            Line::from("Replay:"),
+            Line::from(""),
+            Line::from("Spc  Play / pause"),
+            Line::from("← →  Seek 5s"),
+            Line::from("↑ ↓  Speed"),
+            Line::from(".    Next piece"),
+            Line::from("Home Restart"),
+            Line::from("q    Quit"),
+        ])
+    } else {
+        let mut lines = vec![
+            Line::from("Controls:"),
+            Line::from(""),
+            Line::from("← →  Move"),
+            Line::from("↑ x  Rotate CW"),
+            Line::from("z    Rotate CCW"),
+            Line::from("c    Hold"),
+            Line::from("↓    Soft drop"),
+            Line::from("Spc  Hard drop"),
+            Line::from("p    Pause"),
+            Line::from("s    Save"),
+            Line::from("a    AI mode"),
+            Line::from("q    Quit"),
+        ];
+        if game.config.practice {
+            lines.insert(lines.len() - 2, Line::from("u r  Undo / redo"));
+        }
+        Text::from(lines)
+    };
     let controls_widget = Paragraph::new(controls_text)
         .block(Block::default().borders(Borders::ALL).title(" Help "));
     f.render_widget(controls_widget, sidebar_chunks[3]);
 
     // Game over overlay
     if game.game_over {
-        render_game_over(f, size, game.score);
+        render_game_over(f, size, game, hud);
     }
 
     // Paused overlay
@@ -495,25 +703,134 @@ pub fn render_ui(f: &mut Frame, game: &GameState) {
     }
 }
 
-fn render_game_over(f: &mut Frame, area: Rect, score: u32) {
-    let popup_width = 30u16;
-    let popup_height = 7u16;
-    let popup_x = area.x + area.width.saturating_sub(popup_width) / 2;
-    let popup_y = area.y + area.height.saturating_sub(popup_height) / 2;
-
-    let popup_area = Rect::new(popup_x, popup_y, popup_width.min(area.width), popup_height.min(area.height));
+/// Combo count and back-to-back status for the score panel.
+fn render_chains(game: &GameState) -> Line<'static> {
+    let combo = match game.scorer.combo {
+        Some(n) if n > 0 => Span::styled(format!("Combo x{}", n), Style::default().fg(Color::Yellow)),
+        _ => Span::styled("Combo -", Style::default().fg(Color::DarkGray)),
+    };
+    let b2b = if game.scorer.back_to_back {
+        Span::styled("  B2B", Style::default().fg(Color::Magenta))
+    } else {
+        Span::styled("  B2B", Style::default().fg(Color::DarkGray))
+    };
+    Line::from(vec![combo, b2b])
+}
 
+/// Replay clock, speed and a progress bar.
+fn render_replay_status(f: &mut Frame, status: &ReplayStatus, area: Rect) {
+    let clock = |t: Duration| format!("{}:{:02}", t.as_secs() / 60, t.as_secs() % 60);
+    let (icon, color) = if status.paused { ("‖", Color::Yellow) } else { ("▶", Color::Green) };
     let text = Text::from(vec![
+        Line::from(vec![
+            Span::styled(format!("{} ", icon), Style::default().fg(color)),
+            Span::raw(format!("{} / {}  x{}", clock(status.time), clock(status.duration), status.speed)),
+        ]),
+        Line::from(Span::styled(
+            filled_bar(
+                status.time.as_millis() as u32,
+                status.duration.as_millis() as u32,
+                area.width.saturating_sub(2) as usize,
+                "━",
+                "─",
+            ),
+            Style::default().fg(Color::Cyan),
+        )),
+    ]);
+    let widget = Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(" Replay "));
+    f.render_widget(widget, area);
+}
+
+/// Clear announcement ("T-SPIN DOUBLE") across the upper part of the board.
+fn render_callout(f: &mut Frame, board: Rect, text: &str) {
+    let y = board.y + board.height / 4;
+    if y >= board.y + board.height {
+        return;
+    }
+    let area = Rect::new(board.x, y, board.width, 1);
+    let style = Style::default()
+        .fg(Color::Magenta)
+        .bg(Color::Black)
+        .add_modifier(Modifier::BOLD);
+    let widget = Paragraph::new(Line::from(Span::styled(format!(" {} ", text), style)))
+        .alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+/// Perfect-clear celebration: the board background strobes for a moment,
+/// then a banner stays up until the flash runs out.
+fn render_perfect_clear(f: &mut Frame, board: Rect, remaining: Duration) {
+    let elapsed = PERFECT_CLEAR_FLASH.saturating_sub(remaining).as_millis();
+    let strobe = [Color::LightYellow, Color::LightCyan, Color::LightMagenta];
+    let color = strobe[(elapsed / 100) as usize % strobe.len()];
+    if elapsed < 500 {
+        f.buffer_mut().set_style(board, Style::default().bg(color));
+    }
+
+    let y = board.y + board.height / 2;
+    if y >= board.y + board.height {
+        return;
+    }
+    let area = Rect::new(board.x, y, board.width, 1);
+    let style = Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD);
+    let widget = Paragraph::new(Line::from(Span::styled(" PERFECT CLEAR! ", style))).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+fn render_game_over(f: &mut Frame, area: Rect, game: &GameState, hud: &Hud<'_>) {
+    let mut lines = vec![
         Line::from(""),
         Line::from(Span::styled(
             "  G A M E  O V E R  ",
             Style::default().fg(Color::Red),
         )),
+        Line::from(match game.top_out {
+            Some(reason) => format!("  {}: {}", reason, reason.description()),
+            None => String::new(),
+        }),
         Line::from(""),
-        Line::from(format!("  Final Score: {}", score)),
-        Line::from(""),
-        Line::from("  Press q or Enter to exit"),
-    ]);
+        Line::from(format!("  Final Score: {}", game.score)),
+        Line::from(Span::styled(
+            format!("  Seed: {}", game.seed),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    if game.undo_used {
+        lines.push(Line::from(Span::styled(
+            "  Undo used: no high score",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.push(Line::from(""));
+    match hud {
+        Hud::Replay(_) => lines.push(Line::from("  Press q or Enter to exit")),
+        Hud::Play { name_entry: Some(entry), .. } if !entry.saved => {
+            lines.push(Line::from(Span::styled(
+                format!("  NEW HIGH SCORE!  #{}", entry.rank + 1),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(format!("  Name: {}_", entry.name)));
+            lines.push(Line::from(""));
+            lines.push(Line::from("  Enter to save, Esc to skip"));
+        }
+        Hud::Play { name_entry, .. } => {
+            if let Some(entry) = name_entry {
+                lines.push(Line::from(Span::styled(
+                    format!("  Saved as #{} on the high scores", entry.rank + 1),
+                    Style::default().fg(Color::Green),
+                )));
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from("  Enter for the menu, q to quit"));
+        }
+    }
+
+    let popup_width = 44u16;
+    let popup_height = lines.len() as u16 + 2;
+    let popup_x = area.x + area.width.saturating_sub(popup_width) / 2;
+    let popup_y = area.y + area.height.saturating_sub(popup_height) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width.min(area.width), popup_height.min(area.height));
+    let text = Text::from(lines);
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -521,6 +838,7 @@ fn render_game_over(f: &mut Frame, area: Rect, score: u32) {
         .style(Style::default().bg(Color::Black));
 
     let widget = Paragraph::new(text).block(block);
+    f.render_widget(Clear, popup_area);
     f.render_widget(widget, popup_area);
 }
 
@@ -550,3 +868,91 @@ fn render_paused(f: &mut Frame, area: Rect) {
     let widget = Paragraph::new(text).block(block);
     f.render_widget(widget, popup_area);
 }
+
+/// Rectangle of `width` x `height` centred in `area`, clipped to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let x = area.x + area.width.saturating_sub(width) / 2;
+    let y = area.y + area.height.saturating_sub(height) / 2;
+    Rect::new(x, y, width.min(area.width), height.min(area.height))
+}
+
+/// Start menu: `items` with the `selected` one highlighted.
+pub fn render_menu(f: &mut Frame, items: &[&str], selected: usize) {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "T E T R I S",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for (i, item) in items.iter().enumerate() {
+        lines.push(if i == selected {
+            Line::from(Span::styled(
+                format!("▶ {} ◀", item),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(item.to_string())
+        });
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("↑ ↓ choose, Enter select", Style::default().fg(Color::DarkGray))));
+
+    let area = centered(f.area(), 32, lines.len() as u16 + 2);
+    let widget = Paragraph::new(Text::from(lines))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(widget, area);
+}
+
+/// One high-score table, `key` naming its mode and rules; `page` of
+/// `pages` tells where it sits among the stored tables.
+pub fn render_high_scores(f: &mut Frame, key: &str, entries: &[Entry], page: usize, pages: usize) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(Span::styled(key.to_string(), Style::default().fg(Color::Cyan))),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "{:>3}  {:<12} {:>9} {:>6} {:>6} {:>6}  {:<16}  {}",
+                "#", "Name", "Score", "Lines", "Pieces", "Time", "Date", "Seed"
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    for i in 0..TABLE_SIZE {
+        lines.push(match entries.get(i) {
+            Some(e) => {
+                let secs = e.time().as_secs();
+                Line::from(format!(
+                    "{:>3}  {:<12} {:>9} {:>6} {:>6} {:>3}:{:02}  {:<16}  {}",
+                    i + 1,
+                    e.name,
+                    e.score,
+                    e.lines,
+                    e.pieces,
+                    secs / 60,
+                    secs % 60,
+                    e.date,
+                    e.seed
+                ))
+            }
+            None => Line::from(Span::styled(format!("{:>3}  ---", i + 1), dim)),
+        });
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("← → other tables ({}/{}), Esc back", page + 1, pages.max(1)),
+        dim,
+    )));
+
+    let area = centered(f.area(), 96, lines.len() as u16 + 2);
+    let widget = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" High Scores ")
+            .padding(ratatui::widgets::Padding::horizontal(1)),
+    );
+    f.render_widget(widget, area);
+}